@@ -1,26 +1,129 @@
-use std::sync::mpsc::{Receiver, Sender};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::PathBuf,
+    sync::mpsc::{Receiver, Sender},
+    time::Instant,
+};
 
-use crate::config::set_styles;
+use crate::config::{
+    Settings, apply_settings,
+    file::{ConfigFile, ConfigWatcher},
+    state::{STATE_KEY, STATE_VERSION, SavedState, WindowLayout},
+};
 
-use eframe::App;
-use tmapi::{Attachment, Email};
+use eframe::{App, egui::KeyboardShortcut};
+use tempfile::TempDir;
+use tmgui::{
+    cards::Cards,
+    event_handler::{Event, EventResponse},
+    forward::{ForwardRunner, Forwarding},
+    hooks::{HookLog, HookRunner},
+    links::Link,
+    retention::{OpenedInbox, Retention},
+    rules::{RuleOutcome, RuleRunner},
+    state::{DEFAULT_DOMAIN, InboxState, random_name},
+    vault::VaultKey,
+};
 
-use crate::event_handler::{Event, EventResponse};
+use crate::{
+    app::{
+        actions::Action,
+        files::FileReader,
+        lock::Locked,
+        ui::{
+            command_palette::CommandPalette,
+            email_view::attachment_list::{ArchiveView, Download, Downloaded},
+            preview_window::PreviewWindow,
+        },
+    },
+    ipc::IpcServer,
+};
 
+pub(super) mod actions;
+pub(super) mod files;
+pub(super) mod forward;
+pub(super) mod hooks;
+pub(super) mod lock;
+pub(super) mod retention;
+pub(super) mod rules;
 pub(super) mod ui;
 pub(super) mod utils;
 
 pub struct TempMailApp {
-    domain: String,
-    domains: Vec<String>,
-    name: String,
-    emails: Vec<Email>,
+    inbox: InboxState,
     fetching: bool,
-    viewed_email: Option<Email>,
-    email_count: u32,
-    current_offset: u32,
-    attachments: Option<Vec<Attachment>>,
     images: Vec<(String, String)>,
+    previews: Vec<PreviewWindow>,
+    downloads: Vec<Download>,
+    /// Checked attachments by id.
+    downloaded: HashMap<String, Downloaded>,
+    /// Attachments to download despite the size limit.
+    oversize_allowed: HashSet<String>,
+    /// Inspected archive attachments by id.
+    archives: HashMap<String, ArchiveView>,
+    files: FileReader,
+    /// Calendar events and contacts by attachment id.
+    cards: HashMap<String, Cards>,
+    /// Where a card was last exported to.
+    card_saved: Option<PathBuf>,
+    palette: Option<CommandPalette>,
+    settings: Settings,
+    applied_settings: Option<Settings>,
+    settings_open: bool,
+    new_font_path: String,
+    state_path: String,
+    /// Where the settings were last exported to.
+    exported: Option<PathBuf>,
+    /// An imported file with sensitive settings, waiting for confirmation
+    /// to take those too.
+    pending_import: Option<(PathBuf, Vec<&'static str>)>,
+    recent_addresses: Vec<String>,
+    retention: BTreeMap<String, Retention>,
+    forwarding: BTreeMap<String, Forwarding>,
+    forward_runner: ForwardRunner,
+    forwards_running: usize,
+    /// What the last forwarding did.
+    forward_status: Option<String>,
+    /// The address the tracked expiries and seen emails belong to.
+    watched_address: String,
+    /// The address whose inbox was loaded last, cleared on leaving if its
+    /// retention asks for it.
+    opened: OpenedInbox,
+    /// The address field has focus, which pauses polling.
+    editing_address: bool,
+    /// Emails being deleted because they expired.
+    expiring: HashSet<String>,
+    rule_runner: RuleRunner,
+    rules_running: usize,
+    rules_open: bool,
+    rule_preview: Option<Vec<RuleOutcome>>,
+    /// Ids of the emails already fetched for the address, `None` until its
+    /// first fetch. Rules run on the others.
+    seen: Option<HashSet<String>>,
+    labels: HashMap<String, Vec<String>>,
+    read: HashSet<String>,
+    hook_runner: HookRunner,
+    hooks_running: usize,
+    hooks_open: bool,
+    hook_log: Vec<HookLog>,
+    /// A link waiting for confirmation before it opens.
+    pending_link: Option<Link>,
+    window: WindowLayout,
+    last_poll: Instant,
+    polling: bool,
+    error: Option<String>,
+    profile_dir: Option<PathBuf>,
+    ipc: Option<IpcServer>,
+    /// Set in private mode, holds the downloads and is deleted on drop.
+    private: Option<TempDir>,
+    /// Set while an encrypted state is unlocked.
+    vault_key: Option<VaultKey>,
+    locked: Option<Locked>,
+    passphrase: String,
+    last_activity: Instant,
+    cli_config: ConfigFile,
+    config_watcher: ConfigWatcher,
+    shortcuts: Vec<(Action, KeyboardShortcut)>,
     events: Sender<Event>,
     responses: Receiver<EventResponse>,
 }
@@ -29,42 +132,168 @@ impl TempMailApp {
     pub fn new(
         tx: Sender<Event>,
         rx: Receiver<EventResponse>,
-        name: Option<String>,
-        domain: Option<String>,
+        state: SavedState,
+        cli_config: ConfigFile,
+        profile_dir: Option<PathBuf>,
+        private: bool,
+        error: Option<String>,
     ) -> Self {
-        let name = name.unwrap_or(Self::gen_random_name(10));
-        let domain = domain.unwrap_or("vwh.sh".into());
-        let _ = tx.send(Event::FetchEmails([name.clone(), domain.clone()].join("@")));
-        let _ = tx.send(Event::FetchDomanins);
-
-        Self {
-            name,
-            domain,
+        let mut app = Self {
+            inbox: Default::default(),
             events: tx,
             responses: rx,
             fetching: true,
             images: Default::default(),
-            emails: Default::default(),
-            viewed_email: Default::default(),
-            domains: Default::default(),
-            email_count: Default::default(),
-            current_offset: Default::default(),
-            attachments: Default::default(),
+            previews: Default::default(),
+            downloads: Default::default(),
+            downloaded: Default::default(),
+            oversize_allowed: Default::default(),
+            archives: Default::default(),
+            files: Default::default(),
+            cards: Default::default(),
+            card_saved: None,
+            palette: Default::default(),
+            settings: state.settings,
+            applied_settings: Default::default(),
+            settings_open: Default::default(),
+            new_font_path: Default::default(),
+            state_path: Default::default(),
+            exported: None,
+            pending_import: None,
+            recent_addresses: state.recent_addresses,
+            retention: state.retention,
+            forwarding: state.forwarding,
+            forward_runner: ForwardRunner::spawn(),
+            forwards_running: 0,
+            forward_status: None,
+            watched_address: Default::default(),
+            opened: Default::default(),
+            editing_address: false,
+            expiring: Default::default(),
+            rule_runner: RuleRunner::spawn(),
+            rules_running: 0,
+            rules_open: false,
+            rule_preview: None,
+            hook_runner: HookRunner::spawn(),
+            hooks_running: 0,
+            hooks_open: false,
+            hook_log: Vec::new(),
+            pending_link: None,
+            seen: None,
+            labels: Default::default(),
+            read: Default::default(),
+            window: state.window,
+            last_poll: Instant::now(),
+            polling: Default::default(),
+            error,
+            profile_dir,
+            ipc: Default::default(),
+            private: None,
+            vault_key: None,
+            locked: None,
+            passphrase: Default::default(),
+            last_activity: Instant::now(),
+            cli_config,
+            config_watcher: ConfigWatcher::new(),
+            shortcuts: Default::default(),
+        };
+        app.reload_config();
+        app.set_private(private);
+        app.set_proxy();
+        app.set_api_url();
+        let _ = app.events.send(Event::FetchDomanins);
+        match state.vault {
+            Some(sealed) => {
+                app.locked = Some(Locked {
+                    sealed,
+                    name: state.name,
+                    domain: state.domain,
+                })
+            }
+            None => app.open_inbox(state.name, state.domain),
+        }
+        app
+    }
+}
+
+impl TempMailApp {
+    fn open_inbox(&mut self, name: Option<String>, domain: Option<String>) {
+        let name = name.unwrap_or(random_name(10));
+        let domain = domain
+            .or(self.settings.default_domain())
+            .unwrap_or(DEFAULT_DOMAIN.into());
+        let domains = std::mem::take(&mut self.inbox.domains);
+        self.inbox = InboxState::new(name, domain);
+        self.inbox.domains = domains;
+        let _ = self.events.send(Event::FetchEmails(self.email()));
+    }
+}
+
+impl TempMailApp {
+    fn saved_state(&self) -> SavedState {
+        let mut state = SavedState {
+            version: STATE_VERSION,
+            name: Some(self.inbox.name.clone()),
+            domain: Some(self.inbox.domain.clone()),
+            recent_addresses: self.recent_addresses.clone(),
+            retention: self.retention.clone(),
+            forwarding: self.forwarding.clone(),
+            settings: self.settings.clone(),
+            window: self.window.clone(),
+            vault: None,
+        };
+        if !self.inbox.name.is_empty() {
+            state.remember_address(self.email());
         }
+        state
     }
 }
 
 impl App for TempMailApp {
     fn update(&mut self, ctx: &eframe::egui::Context, _frame: &mut eframe::Frame) {
         egui_extras::install_image_loaders(ctx);
-        set_styles(ctx);
+        self.watch_config(ctx);
+        if self.applied_settings.as_ref() != Some(&self.settings) {
+            apply_settings(ctx, &self.settings, self.applied_settings.as_ref());
+            if let Some(applied) = &self.applied_settings
+                && applied.proxy() != self.settings.proxy()
+            {
+                self.set_proxy();
+            }
+            if let Some(applied) = &self.applied_settings
+                && applied.api_url() != self.settings.api_url()
+            {
+                self.set_api_url();
+            }
+            self.applied_settings = Some(self.settings.clone());
+        }
+        ctx.input(|i| self.window.update(i.viewport()));
         self.handle_responses();
+        self.handle_rule_reports(ctx);
+        self.handle_hook_logs(ctx);
+        self.handle_forward_reports(ctx);
+        self.handle_file_results(ctx);
+        self.sync_ipc(ctx);
+        self.auto_lock(ctx);
+        if self.locked.is_none() {
+            self.watch_address();
+            self.expire(ctx);
+        }
+        if self.locked.is_none() {
+            self.handle_shortcuts(ctx);
+        }
+        self.poll(ctx);
         self.app_ui(ctx);
     }
 
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
-        storage.set_string("name", self.name.clone());
-        storage.set_string("domain", self.domain.clone());
+        if self.private.is_some() {
+            return;
+        }
+        let mut state = self.saved_state();
+        self.recent_addresses = state.recent_addresses.clone();
+        self.seal(&mut state);
+        eframe::set_value(storage, STATE_KEY, &state);
     }
 
     fn auto_save_interval(&self) -> std::time::Duration {
@@ -72,6 +301,6 @@ impl App for TempMailApp {
     }
 
     fn persist_egui_memory(&self) -> bool {
-        true
+        self.private.is_none()
     }
 }
@@ -0,0 +1,215 @@
+use eframe::egui::{self, Button, Theme};
+
+use tmgui::{
+    event_handler::Event,
+    i18n::{tr, tr_with},
+    state::random_name,
+};
+
+use crate::{app::TempMailApp, config::ThemeChoice};
+
+#[derive(Clone, PartialEq)]
+pub enum Action {
+    CopyAddress,
+    NewRandomAddress,
+    SwitchDomain(String),
+    SwitchAddress(String),
+    Refresh,
+    DeleteAllEmails,
+    SwitchTheme,
+    OpenSettings,
+    ExportSettings,
+    OpenRules,
+    OpenHooks,
+    OpenEmail { id: String, subject: String },
+    ForwardEmail,
+    CommandPalette,
+    TogglePrivateMode,
+    Lock,
+    Panic,
+    Exit,
+}
+
+impl Action {
+    /// Looks up the actions that shortcuts can be bound to in the config file.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "copy_address" => Self::CopyAddress,
+            "new_random_address" => Self::NewRandomAddress,
+            "refresh" => Self::Refresh,
+            "delete_all_emails" => Self::DeleteAllEmails,
+            "switch_theme" => Self::SwitchTheme,
+            "open_settings" => Self::OpenSettings,
+            "export_settings" => Self::ExportSettings,
+            "open_rules" => Self::OpenRules,
+            "open_hooks" => Self::OpenHooks,
+            "forward_email" => Self::ForwardEmail,
+            "command_palette" => Self::CommandPalette,
+            "toggle_private_mode" => Self::TogglePrivateMode,
+            "lock" => Self::Lock,
+            "panic" => Self::Panic,
+            "exit" => Self::Exit,
+            _ => return None,
+        })
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Action::CopyAddress => tr("action_copy_address"),
+            Action::NewRandomAddress => tr("action_new_random_address"),
+            Action::SwitchDomain(domain) => tr_with("action_switch_domain", &[("domain", domain)]),
+            Action::SwitchAddress(address) => {
+                tr_with("action_switch_address", &[("address", address)])
+            }
+            Action::Refresh => tr("action_refresh"),
+            Action::DeleteAllEmails => tr("action_delete_all"),
+            Action::SwitchTheme => tr("action_switch_theme"),
+            Action::OpenSettings => tr("action_open_settings"),
+            Action::ExportSettings => tr("action_export_settings"),
+            Action::OpenRules => tr("action_open_rules"),
+            Action::OpenHooks => tr("action_open_hooks"),
+            Action::OpenEmail { subject, .. } => {
+                tr_with("action_open_email", &[("subject", subject)])
+            }
+            Action::ForwardEmail => tr("action_forward_email"),
+            Action::CommandPalette => tr("action_command_palette"),
+            Action::TogglePrivateMode => tr("action_toggle_private_mode"),
+            Action::Lock => tr("action_lock"),
+            Action::Panic => tr("action_panic"),
+            Action::Exit => tr("action_exit"),
+        }
+    }
+}
+
+impl TempMailApp {
+    pub(super) fn actions(&self) -> Vec<Action> {
+        let mut actions = vec![Action::CopyAddress, Action::NewRandomAddress];
+        actions.extend(
+            self.inbox
+                .domains
+                .iter()
+                .filter(|domain| **domain != self.inbox.domain)
+                .cloned()
+                .map(Action::SwitchDomain),
+        );
+        let current = self.email();
+        actions.extend(
+            self.recent_addresses
+                .iter()
+                .filter(|address| **address != current)
+                .cloned()
+                .map(Action::SwitchAddress),
+        );
+        actions.extend([
+            Action::Refresh,
+            Action::DeleteAllEmails,
+            Action::SwitchTheme,
+            Action::OpenSettings,
+            Action::ExportSettings,
+            Action::OpenRules,
+            Action::OpenHooks,
+        ]);
+        actions.extend(self.inbox.emails.iter().map(|email| Action::OpenEmail {
+            id: email.id.clone(),
+            subject: email.subject.clone(),
+        }));
+        actions.extend([
+            Action::ForwardEmail,
+            Action::CommandPalette,
+            Action::TogglePrivateMode,
+            Action::Lock,
+            Action::Panic,
+            Action::Exit,
+        ]);
+        actions.retain(|action| self.action_enabled(action));
+        actions
+    }
+
+    pub(super) fn action_enabled(&self, action: &Action) -> bool {
+        match action {
+            Action::Refresh => !self.inbox.name.is_empty(),
+            Action::DeleteAllEmails => !self.inbox.name.is_empty() && !self.inbox.emails.is_empty(),
+            Action::Lock => self.vault_key.is_some(),
+            Action::ForwardEmail => {
+                self.inbox.viewed_email.is_some() && self.forwarding_rule().validate().is_ok()
+            }
+            _ => true,
+        }
+    }
+
+    pub(super) fn run_action(&mut self, ctx: &egui::Context, action: Action) {
+        match action {
+            Action::CopyAddress => ctx.copy_text(self.email()),
+            Action::NewRandomAddress => self.inbox.name = random_name(10),
+            Action::SwitchDomain(domain) => self.inbox.domain = domain,
+            Action::SwitchAddress(address) => {
+                if let Some((name, domain)) = address.split_once('@') {
+                    self.inbox.name = name.into();
+                    self.inbox.domain = domain.into();
+                    self.run_action(ctx, Action::Refresh);
+                }
+            }
+            Action::Refresh => {
+                self.send_event(Event::FetchEmails(self.email()));
+                self.send_event(Event::CountEmails(self.email()));
+            }
+            Action::DeleteAllEmails => {
+                self.send_event(Event::DeleteAllEmails(self.email()));
+                self.send_event(Event::FetchEmails(self.email()));
+            }
+            Action::SwitchTheme => {
+                self.settings.theme = match ctx.theme() {
+                    Theme::Dark => ThemeChoice::Light,
+                    Theme::Light => ThemeChoice::Dark,
+                }
+            }
+            Action::OpenSettings => self.settings_open = true,
+            // The settings window shows where the file went.
+            Action::ExportSettings => {
+                self.export_settings();
+                self.settings_open = true;
+            }
+            Action::OpenRules => self.rules_open = true,
+            Action::OpenHooks => self.hooks_open = true,
+            Action::OpenEmail { id, .. } => {
+                self.close_email();
+                self.send_event(Event::FetchEmail(id));
+            }
+            Action::ForwardEmail => {
+                if let Some(email) = self.inbox.viewed_email.clone() {
+                    self.forward(vec![email]);
+                }
+            }
+            Action::CommandPalette => self.palette = Some(Default::default()),
+            Action::TogglePrivateMode => self.set_private(self.private.is_none()),
+            Action::Lock => self.lock(ctx),
+            Action::Panic => self.panic_wipe(ctx),
+            Action::Exit => ctx.send_viewport_cmd(egui::ViewportCommand::Close),
+        }
+    }
+
+    pub(super) fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        for (action, shortcut) in self.shortcuts.clone() {
+            if !ctx.input_mut(|i| i.consume_shortcut(&shortcut)) || !self.action_enabled(&action) {
+                continue;
+            }
+            match action {
+                Action::CommandPalette if self.palette.is_some() => self.palette = None,
+                action => self.run_action(ctx, action),
+            }
+        }
+    }
+
+    pub(super) fn action_button(&mut self, ui: &mut egui::Ui, action: Action) {
+        if !self.action_enabled(&action) {
+            return;
+        }
+        let mut button = Button::new(action.label());
+        if let Some((_, shortcut)) = self.shortcuts.iter().find(|(bound, _)| *bound == action) {
+            button = button.shortcut_text(ui.ctx().format_shortcut(shortcut));
+        }
+        if ui.add(button).clicked() {
+            self.run_action(ui.ctx(), action);
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use std::{
+    io,
+    path::PathBuf,
+    sync::mpsc::{Receiver, Sender, channel},
+    thread,
+    time::Duration,
+};
+
+use eframe::egui;
+use tmgui::{
+    archives::{self, Entry, Kind},
+    i18n::tr_with,
+    previews::{Format, Preview},
+};
+
+use crate::app::TempMailApp;
+
+/// Reads downloaded files on threads, as archives and large files would
+/// stall the UI.
+pub struct FileReader {
+    results: Sender<FileResult>,
+    finished: Receiver<FileResult>,
+    running: usize,
+}
+
+/// What a [`FileReader`] thread read, archives by attachment id.
+pub enum FileResult {
+    Listed(String, io::Result<Vec<Entry>>),
+    /// An entry and its text, `None` if it is binary.
+    Previewed(String, String, io::Result<Option<String>>),
+    /// The directory the entries were extracted to.
+    Extracted(String, PathBuf, io::Result<Vec<PathBuf>>),
+    /// A file for a [`PreviewWindow`](crate::app::ui::preview_window::PreviewWindow).
+    Loaded(PathBuf, Result<Preview, String>),
+}
+
+impl Default for FileReader {
+    fn default() -> Self {
+        let (results, finished) = channel();
+        Self {
+            results,
+            finished,
+            running: 0,
+        }
+    }
+}
+
+impl FileReader {
+    pub fn run(&mut self, read: impl FnOnce() -> FileResult + Send + 'static) {
+        self.running += 1;
+        let results = self.results.clone();
+        thread::spawn(move || results.send(read()));
+    }
+
+    pub fn load(&mut self, path: PathBuf, format: Format) {
+        self.run(move || {
+            let preview = Preview::load(&path, format);
+            FileResult::Loaded(path, preview)
+        });
+    }
+
+    pub fn list(&mut self, id: String, path: PathBuf, kind: Kind) {
+        self.run(move || FileResult::Listed(id, archives::list(&path, kind)));
+    }
+
+    pub fn preview(&mut self, id: String, path: PathBuf, kind: Kind, name: String) {
+        self.run(move || {
+            let text = archives::preview(&path, kind, &name);
+            FileResult::Previewed(id, name, text)
+        });
+    }
+
+    pub fn extract(
+        &mut self,
+        id: String,
+        path: PathBuf,
+        kind: Kind,
+        names: Vec<String>,
+        dir: PathBuf,
+    ) {
+        self.run(move || {
+            let extracted = archives::extract(&path, kind, &names, &dir);
+            FileResult::Extracted(id, dir, extracted)
+        });
+    }
+}
+
+impl TempMailApp {
+    /// Shows what the [`FileReader`] threads read. Results for archives and
+    /// previews that were closed in the meantime are dropped.
+    pub(super) fn handle_file_results(&mut self, ctx: &egui::Context) {
+        if self.files.running > 0 {
+            ctx.request_repaint_after(Duration::from_millis(100));
+        }
+        while let Ok(result) = self.files.finished.try_recv() {
+            self.files.running = self.files.running.saturating_sub(1);
+            match result {
+                FileResult::Listed(id, entries) => {
+                    let Some(view) = self.archives.get_mut(&id) else {
+                        continue;
+                    };
+                    view.busy = false;
+                    match entries {
+                        Ok(entries) => view.entries = entries,
+                        Err(e) => {
+                            let (path, error) = (view.path.display().to_string(), e.to_string());
+                            self.archives.remove(&id);
+                            self.error = Some(tr_with(
+                                "attachment_read_failed",
+                                &[("path", &path), ("error", &error)],
+                            ));
+                        }
+                    }
+                }
+                FileResult::Previewed(id, name, text) => {
+                    let Some(view) = self.archives.get_mut(&id) else {
+                        continue;
+                    };
+                    view.busy = false;
+                    match text {
+                        Ok(text) => view.preview = Some((name, text)),
+                        Err(e) => {
+                            let error = e.to_string();
+                            self.error = Some(tr_with(
+                                "attachment_read_failed",
+                                &[("path", &name), ("error", &error)],
+                            ));
+                        }
+                    }
+                }
+                FileResult::Extracted(id, dir, extracted) => {
+                    let Some(view) = self.archives.get_mut(&id) else {
+                        continue;
+                    };
+                    view.busy = false;
+                    match extracted {
+                        Ok(_) => view.extracted = Some(dir),
+                        Err(e) => {
+                            let error = e.to_string();
+                            self.error =
+                                Some(tr_with("attachment_extract_failed", &[("error", &error)]));
+                        }
+                    }
+                }
+                FileResult::Loaded(path, preview) => {
+                    let Some(index) = self.previews.iter().position(|w| w.path == path) else {
+                        continue;
+                    };
+                    match preview {
+                        Ok(preview) => self.previews[index].preview = Some(preview),
+                        Err(e) => {
+                            self.previews.remove(index);
+                            self.error = Some(e);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use std::time::Duration;
+
+use eframe::egui;
+use tmapi::Email;
+use tmgui::{
+    forward::{ForwardJob, Forwarding},
+    i18n::tr_with,
+};
+
+use crate::app::TempMailApp;
+
+impl TempMailApp {
+    pub(super) fn forwarding_rule(&self) -> Forwarding {
+        self.forwarding
+            .get(&self.email())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub(super) fn set_forwarding_rule(&mut self, forwarding: Forwarding) {
+        if forwarding.is_empty() {
+            self.forwarding.remove(&self.email());
+        } else {
+            self.forwarding.insert(self.email(), forwarding);
+        }
+    }
+
+    /// Forwards emails of the current address to its destination.
+    pub(super) fn forward(&mut self, emails: Vec<Email>) {
+        self.forwards_running += 1;
+        self.forward_status = None;
+        self.forward_runner.run(ForwardJob {
+            forwarding: self.forwarding_rule(),
+            emails,
+            proxy: self.settings.proxy().into(),
+            api_url: self.settings.api_url().into(),
+            max_download: self.settings.max_download_bytes(),
+        });
+    }
+
+    pub(super) fn handle_forward_reports(&mut self, ctx: &egui::Context) {
+        if self.forwards_running > 0 {
+            ctx.request_repaint_after(Duration::from_millis(500));
+        }
+        while let Some(report) = self.forward_runner.try_report() {
+            self.forwards_running = self.forwards_running.saturating_sub(1);
+            let count = report.forwarded.len().to_string();
+            self.forward_status = Some(tr_with("forward_sent", &[("count", &count)]));
+            if !report.errors.is_empty() {
+                self.error = Some(report.errors.join("; "));
+            }
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use std::time::Duration;
+
+use eframe::egui;
+use tmapi::Email;
+
+use tmgui::hooks::HookJob;
+
+use crate::app::TempMailApp;
+
+/// Log entries kept for the hooks window.
+const HOOK_LOG_LIMIT: usize = 200;
+
+impl TempMailApp {
+    pub(super) fn hooks_configured(&self) -> bool {
+        !self.settings.hook_command.trim().is_empty() || !self.settings.hook_url.trim().is_empty()
+    }
+
+    pub(super) fn run_hooks(&mut self, emails: Vec<Email>) {
+        if !self.hooks_configured() {
+            return;
+        }
+        self.hooks_running += 1;
+        self.hook_runner.run(HookJob {
+            address: self.email(),
+            emails,
+            command: self.settings.hook_command.clone(),
+            url: self.settings.hook_url.clone(),
+            proxy: self.settings.proxy().into(),
+            api_url: self.settings.api_url().into(),
+        });
+    }
+
+    pub(super) fn handle_hook_logs(&mut self, ctx: &egui::Context) {
+        if self.hooks_running > 0 {
+            ctx.request_repaint_after(Duration::from_millis(500));
+        }
+        while let Some(logs) = self.hook_runner.try_report() {
+            self.hooks_running = self.hooks_running.saturating_sub(1);
+            self.hook_log.extend(logs);
+        }
+        let excess = self.hook_log.len().saturating_sub(HOOK_LOG_LIMIT);
+        self.hook_log.drain(..excess);
+    }
+}
@@ -0,0 +1,154 @@
+use std::{
+    io,
+    time::{Duration, Instant},
+};
+
+use eframe::egui;
+use tmgui::{i18n::tr_with, state::InboxState, vault::VaultKey};
+use zeroize::Zeroizing;
+
+use crate::{
+    app::TempMailApp,
+    config::state::{self, SavedState},
+};
+
+/// The sealed addresses while the app is locked.
+pub struct Locked {
+    pub sealed: String,
+    /// An address given on the command line, opened after unlocking.
+    pub name: Option<String>,
+    pub domain: Option<String>,
+}
+
+impl TempMailApp {
+    pub(super) fn unlock(&mut self) {
+        let Some(locked) = self.locked.take() else {
+            return;
+        };
+        let passphrase = Zeroizing::new(std::mem::take(&mut self.passphrase));
+        let mut state = SavedState {
+            name: locked.name.clone(),
+            domain: locked.domain.clone(),
+            vault: Some(locked.sealed.clone()),
+            ..Default::default()
+        };
+        match state.unlock(&passphrase) {
+            Ok(key) => {
+                self.vault_key = key;
+                self.recent_addresses = state.recent_addresses;
+                self.retention = state.retention;
+                self.forwarding = state.forwarding;
+                self.last_activity = Instant::now();
+                self.error = None;
+                self.open_inbox(state.name, state.domain);
+            }
+            Err(message) => {
+                self.locked = Some(locked);
+                self.error = Some(message);
+            }
+        }
+    }
+
+    /// Seals the addresses and forgets them and the key until the passphrase
+    /// is entered again. The local API stops and the cached attachments are
+    /// deleted, as both would show the mail of the sealed address.
+    pub(super) fn lock(&mut self, ctx: &egui::Context) {
+        let Some(key) = self.vault_key.take() else {
+            return;
+        };
+        let mut state = self.saved_state();
+        state.seal(&key);
+        self.locked = Some(Locked {
+            sealed: state.vault.unwrap_or_default(),
+            name: None,
+            domain: None,
+        });
+        let domains = std::mem::take(&mut self.inbox.domains);
+        self.inbox = InboxState {
+            domains,
+            ..Default::default()
+        };
+        self.recent_addresses.clear();
+        self.retention.clear();
+        self.forwarding.clear();
+        self.forget_emails();
+        self.images.clear();
+        self.previews.clear();
+        self.downloads.clear();
+        self.downloaded.clear();
+        self.oversize_allowed.clear();
+        self.archives.clear();
+        self.cards.clear();
+        self.card_saved = None;
+        self.palette = None;
+        ctx.forget_all_images();
+        self.ipc = None;
+        self.purge_cache();
+    }
+
+    fn purge_cache(&mut self) {
+        if self.private.is_some() {
+            // A new private directory replaces the old, which is deleted.
+            self.set_private(true);
+            return;
+        }
+        let Some(dir) = state::cache_dir() else {
+            return;
+        };
+        match std::fs::remove_dir_all(&dir) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                let (path, error) = (dir.display().to_string(), e.to_string());
+                self.error = Some(tr_with(
+                    "lock_purge_failed",
+                    &[("path", &path), ("error", &error)],
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    pub(super) fn auto_lock(&mut self, ctx: &egui::Context) {
+        if ctx.input(|i| !i.events.is_empty() || i.pointer.is_moving()) {
+            self.last_activity = Instant::now();
+        }
+        let minutes = self.settings.auto_lock_minutes;
+        if self.vault_key.is_none() || minutes == 0 {
+            return;
+        }
+        let timeout = Duration::from_secs(60 * u64::from(minutes));
+        let idle = self.last_activity.elapsed();
+        if idle >= timeout {
+            self.lock(ctx);
+        } else {
+            ctx.request_repaint_after(timeout - idle);
+        }
+    }
+
+    pub(super) fn set_passphrase(&mut self) {
+        let passphrase = Zeroizing::new(std::mem::take(&mut self.passphrase));
+        match VaultKey::new(&passphrase) {
+            Ok(key) => self.vault_key = Some(key),
+            Err(message) => self.error = Some(message),
+        }
+    }
+
+    /// Encrypts the addresses of a state about to be stored, or stores the
+    /// sealed ones while locked. Without a vault, SMTP passwords are left
+    /// out so they never reach the disk in plain text.
+    pub(super) fn seal(&self, state: &mut SavedState) {
+        if let Some(key) = &self.vault_key {
+            state.seal(key);
+        } else if let Some(locked) = &self.locked {
+            state.name = None;
+            state.domain = None;
+            state.recent_addresses.clear();
+            state.retention.clear();
+            state.forwarding.clear();
+            state.vault = Some(locked.sealed.clone());
+        } else {
+            for forwarding in state.forwarding.values_mut() {
+                forwarding.password.clear();
+            }
+        }
+    }
+}
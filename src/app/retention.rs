@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+use eframe::egui;
+use tmgui::event_handler::Event;
+
+use tmgui::retention::Retention;
+
+use crate::app::TempMailApp;
+
+const EXPIRY_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+impl TempMailApp {
+    pub(super) fn retention_rule(&self) -> Retention {
+        self.retention
+            .get(&self.email())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub(super) fn set_retention_rule(&mut self, rule: Retention) {
+        if rule.is_empty() {
+            self.retention.remove(&self.email());
+        } else {
+            self.retention.insert(self.email(), rule);
+        }
+    }
+
+    /// Deletes the emails of the current address that are older than its
+    /// rule allows.
+    pub(super) fn expire(&mut self, ctx: &egui::Context) {
+        let rule = self.retention_rule();
+        if rule.max_age_hours == 0 {
+            return;
+        }
+        ctx.request_repaint_after(EXPIRY_CHECK_INTERVAL);
+        let now = chrono::Utc::now().timestamp();
+        let expired = self
+            .inbox
+            .emails
+            .iter()
+            .filter(|email| {
+                rule.expired(email.received_at, now) && !self.expiring.contains(&email.id)
+            })
+            .map(|email| email.id.clone())
+            .collect::<Vec<_>>();
+        for id in expired {
+            self.expiring.insert(id.clone());
+            self.send_event(Event::DeleteEmail(id));
+        }
+    }
+
+    /// Leaves the viewed email, deleting it if the rule burns emails after
+    /// reading.
+    pub(super) fn close_email(&mut self) {
+        self.inbox.attachments = None;
+        let Some(email) = self.inbox.viewed_email.take() else {
+            return;
+        };
+        if self.retention_rule().burn_after_reading {
+            self.send_event(Event::DeleteEmail(email.id));
+        }
+    }
+
+    /// Forgets what was tracked for the previous address when the address
+    /// changes.
+    pub(super) fn watch_address(&mut self) {
+        let address = self.email();
+        if address != self.watched_address {
+            self.watched_address = address;
+            self.expiring.clear();
+            self.seen = None;
+        }
+    }
+
+    /// Clears the previously opened address once the emails of another one
+    /// were loaded, if its rule asks for it. Editing the address alone
+    /// leaves nothing.
+    pub(super) fn inbox_opened(&mut self, address: &str) {
+        let Some(left) = self.opened.open(address) else {
+            return;
+        };
+        if self
+            .retention
+            .get(&left)
+            .is_some_and(|rule| rule.clear_on_leave)
+        {
+            self.send_event(Event::DeleteAllEmails(left));
+            // The deletion empties the shown list, which is loaded again.
+            self.send_event(Event::FetchEmails(address.to_owned()));
+        }
+    }
+}
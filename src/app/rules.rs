@@ -0,0 +1,126 @@
+use std::time::Duration;
+
+use eframe::egui::{self, UserAttentionType, ViewportCommand};
+use log::warn;
+use tmapi::Email;
+use tmgui::rules::{RuleAction, RuleJob, RuleOutcome};
+
+use crate::app::TempMailApp;
+
+impl TempMailApp {
+    /// Runs the hooks, forwarding and rules on the fetched emails that were
+    /// not there before.
+    pub(super) fn new_emails(&mut self) {
+        let Some(seen) = &mut self.seen else {
+            self.seen = Some(self.inbox.emails.iter().map(|e| e.id.clone()).collect());
+            return;
+        };
+        let new = self
+            .inbox
+            .emails
+            .iter()
+            .filter(|email| seen.insert(email.id.clone()))
+            .cloned()
+            .collect::<Vec<_>>();
+        if new.is_empty() {
+            return;
+        }
+        self.run_hooks(new.clone());
+        if self.forwarding_rule().automatic {
+            self.forward(new.clone());
+        }
+        if self.settings.rules.iter().any(|rule| rule.enabled) {
+            self.run_rules(new, false);
+        }
+    }
+
+    pub(super) fn run_rules(&mut self, emails: Vec<Email>, dry_run: bool) {
+        self.rules_running += 1;
+        self.rule_runner.run(RuleJob {
+            emails,
+            rules: self.settings.rules.clone(),
+            dry_run,
+            proxy: self.settings.proxy().into(),
+            api_url: self.settings.api_url().into(),
+            download_dir: self.download_dir(),
+            max_download: self.settings.max_download_bytes(),
+        });
+    }
+
+    pub(super) fn handle_rule_reports(&mut self, ctx: &egui::Context) {
+        if self.rules_running > 0 {
+            ctx.request_repaint_after(Duration::from_millis(200));
+        }
+        while let Some(report) = self.rule_runner.try_report() {
+            self.rules_running = self.rules_running.saturating_sub(1);
+            if !report.errors.is_empty() {
+                self.error = Some(report.errors.join("; "));
+            }
+            if report.dry_run {
+                self.rule_preview = Some(report.outcomes);
+                continue;
+            }
+            for outcome in report.outcomes {
+                self.apply_outcome(ctx, outcome);
+            }
+        }
+    }
+
+    /// Forgets what the rules and reading did to the emails.
+    pub(super) fn forget_emails(&mut self) {
+        self.seen = None;
+        self.labels.clear();
+        self.read.clear();
+        self.rule_preview = None;
+    }
+
+    fn apply_outcome(&mut self, ctx: &egui::Context, mut outcome: RuleOutcome) {
+        let id = outcome.email.id.clone();
+        for action in &outcome.actions {
+            match action {
+                RuleAction::Delete => {
+                    let count = self.inbox.emails.len();
+                    self.inbox.emails.retain(|email| email.id != id);
+                    if self.inbox.emails.len() < count {
+                        self.inbox.email_count = self.inbox.email_count.saturating_sub(1);
+                    }
+                }
+                RuleAction::Label(label) => {
+                    let labels = self.labels.entry(id.clone()).or_default();
+                    if !label.is_empty() && !labels.contains(label) {
+                        labels.push(label.clone());
+                    }
+                }
+                RuleAction::MarkRead => {
+                    self.read.insert(id.clone());
+                }
+                RuleAction::Notify => {
+                    let shown = notify_rust::Notification::new()
+                        .appname("tmgui")
+                        .summary(&outcome.email.subject)
+                        .body(&outcome.email.from_address)
+                        .show();
+                    if let Err(e) = shown {
+                        warn!("Could not show a notification: {e}");
+                    }
+                    ctx.send_viewport_cmd(ViewportCommand::RequestUserAttention(
+                        UserAttentionType::Informational,
+                    ));
+                }
+                RuleAction::CopyCode => {
+                    if let Some(code) = &outcome.code {
+                        ctx.copy_text(code.clone());
+                    }
+                }
+                // Shown in the link dialog rather than opened, like a click in
+                // the email.
+                RuleAction::OpenLink => {
+                    if let Some(link) = outcome.link.take() {
+                        self.confirm_link(link);
+                    }
+                }
+                RuleAction::DownloadAttachments => {}
+            }
+        }
+    }
+}
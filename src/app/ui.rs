@@ -1,17 +1,34 @@
-use crate::app::TempMailApp;
+use crate::app::{TempMailApp, actions::Action};
 use eframe::egui::{self, CentralPanel, MenuBar, Spinner, TopBottomPanel, Widget, Window};
+use tmgui::i18n::tr;
 
-use crate::event_handler::Event;
-
+pub mod command_palette;
 pub mod email_list;
 pub mod email_view;
+pub mod hooks_window;
+pub mod link_dialog;
+pub mod lock_screen;
+pub mod preview_window;
+pub mod rules_window;
+pub mod settings_window;
 pub mod top_bar;
 
 impl TempMailApp {
     pub(super) fn app_ui(&mut self, ctx: &egui::Context) {
+        if self.locked.is_some() {
+            self.lock_screen(ctx);
+            return;
+        }
         self.images(ctx);
+        self.preview_windows(ctx);
         self.menu_bar(ctx);
         self.body(ctx);
+        self.settings_window(ctx);
+        self.rules_window(ctx);
+        self.hooks_window(ctx);
+        self.command_palette(ctx);
+        self.link_dialog(ctx);
+        self.import_dialog(ctx);
     }
 }
 
@@ -20,7 +37,7 @@ impl TempMailApp {
         for (index, (path, name)) in self.images.clone().iter().enumerate() {
             Window::new(name).show(ctx, |ui| {
                 ui.image(path);
-                if ui.button("close").clicked() {
+                if ui.button(tr("image_close")).clicked() {
                     self.images.remove(index);
                 }
             });
@@ -29,16 +46,29 @@ impl TempMailApp {
     fn menu_bar(&mut self, ctx: &egui::Context) {
         TopBottomPanel::top("menubar").show(ctx, |ui| {
             MenuBar::new().ui(ui, |ui| {
-                if ui.button("Exit").clicked() {
-                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
-                }
+                self.action_button(ui, Action::Exit);
+                ui.separator();
+                self.action_button(ui, Action::CommandPalette);
+                self.action_button(ui, Action::SwitchTheme);
+                self.action_button(ui, Action::OpenSettings);
+                self.action_button(ui, Action::OpenRules);
+                self.action_button(ui, Action::OpenHooks);
                 ui.separator();
-                if !self.name.is_empty()
-                    && !self.emails.is_empty()
-                    && ui.button("Delete all emails").clicked()
-                {
-                    self.send_event(Event::DeleteAllEmails(self.email()));
-                    self.send_event(Event::FetchEmails(self.email()));
+                self.action_button(ui, Action::DeleteAllEmails);
+                ui.separator();
+                self.action_button(ui, Action::TogglePrivateMode);
+                self.action_button(ui, Action::Lock);
+                self.action_button(ui, Action::Panic);
+                if self.private.is_some() {
+                    ui.label(tr("private_mode_on"))
+                        .on_hover_text(tr("private_mode_hint"));
+                }
+                if let Some(error) = self.error.clone() {
+                    ui.separator();
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                    if ui.small_button("✖").clicked() {
+                        self.error = None;
+                    }
                 }
             })
         });
@@ -48,22 +78,18 @@ impl TempMailApp {
             ui.vertical_centered_justified(|ui| {
                 ui.set_width(ui.available_width());
 
-                if self.viewed_email.is_some() && ui.button("Back to email list").clicked() {
-                    self.viewed_email = None;
-                    self.attachments = None;
-                } else if self.viewed_email.is_none() {
+                if self.inbox.viewed_email.is_some() && ui.button(tr("view_back")).clicked() {
+                    self.close_email();
+                } else if self.inbox.viewed_email.is_none() {
                     self.top_bar(ui, ctx);
-                    if !self.name.is_empty() && ui.button("Fetch emails").clicked() {
-                        self.send_event(Event::FetchEmails(self.email()));
-                        self.send_event(Event::CountEmails(self.email()));
-                    }
+                    self.action_button(ui, Action::Refresh);
                 }
 
                 ui.spacing();
 
                 if self.fetching {
                     ui.centered_and_justified(|ui| Spinner::new().size(50.).ui(ui));
-                } else if let Some(email) = self.viewed_email.clone() {
+                } else if let Some(email) = self.inbox.viewed_email.clone() {
                     self.email_view(ui, email);
                 } else {
                     self.email_list(ui);
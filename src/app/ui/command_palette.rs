@@ -0,0 +1,98 @@
+use crate::app::{TempMailApp, actions::Action};
+use tmgui::i18n::tr;
+
+use eframe::egui::{
+    self, Align2, Id, Key, KeyboardShortcut, Modifiers, ScrollArea, TextEdit, Window, vec2,
+};
+
+pub const PALETTE_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::K);
+
+#[derive(Default)]
+pub struct CommandPalette {
+    query: String,
+    selected: usize,
+}
+
+impl TempMailApp {
+    pub(super) fn command_palette(&mut self, ctx: &egui::Context) {
+        let Some(mut palette) = self.palette.take() else {
+            return;
+        };
+
+        let matches = self.palette_matches(&palette.query);
+        let (up, down, enter, escape) = ctx.input_mut(|i| {
+            (
+                i.consume_key(Modifiers::NONE, Key::ArrowUp),
+                i.consume_key(Modifiers::NONE, Key::ArrowDown),
+                i.consume_key(Modifiers::NONE, Key::Enter),
+                i.consume_key(Modifiers::NONE, Key::Escape),
+            )
+        });
+        if up {
+            palette.selected = palette.selected.saturating_sub(1);
+        }
+        if down {
+            palette.selected += 1;
+        }
+        palette.selected = palette.selected.min(matches.len().saturating_sub(1));
+
+        let mut chosen = None;
+        Window::new(tr("action_command_palette"))
+            .id(Id::new("command_palette"))
+            .title_bar(false)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_TOP, vec2(0., 60.))
+            .show(ctx, |ui| {
+                let edit = ui.add(
+                    TextEdit::singleline(&mut palette.query)
+                        .hint_text(tr("palette_hint"))
+                        .desired_width(f32::INFINITY),
+                );
+                edit.request_focus();
+                if edit.changed() {
+                    palette.selected = 0;
+                }
+                ui.separator();
+                ScrollArea::vertical().max_height(300.).show(ui, |ui| {
+                    if matches.is_empty() {
+                        ui.weak(tr("palette_no_matches"));
+                    }
+                    for (index, action) in matches.iter().enumerate() {
+                        let is_selected = index == palette.selected;
+                        let row = ui.selectable_label(is_selected, action.label());
+                        if is_selected && (up || down) {
+                            row.scroll_to_me(None);
+                        }
+                        if row.clicked() {
+                            chosen = Some(action.clone());
+                        }
+                    }
+                });
+            });
+
+        if escape {
+            return;
+        }
+        if enter {
+            chosen = matches.get(palette.selected).cloned();
+        }
+        match chosen {
+            Some(action) => self.run_action(ctx, action),
+            None => self.palette = Some(palette),
+        }
+    }
+}
+
+impl TempMailApp {
+    fn palette_matches(&self, query: &str) -> Vec<Action> {
+        let mut matches = self
+            .actions()
+            .into_iter()
+            .filter(|action| *action != Action::CommandPalette)
+            .filter_map(|action| Some((Self::fuzzy_score(query, &action.label())?, action)))
+            .collect::<Vec<_>>();
+        matches.sort_by_key(|(score, _)| -score);
+        matches.into_iter().map(|(_, action)| action).collect()
+    }
+}
@@ -1,31 +1,41 @@
+use std::time::Duration;
+
 use crate::app::TempMailApp;
 
-use eframe::egui::{self, Frame, ScrollArea, Stroke};
+use eframe::egui::{self, Frame, RichText, ScrollArea, Stroke};
 use tmapi::Email;
 
-use crate::event_handler::Event;
+use tmgui::{dates::DateFormat, event_handler::Event, i18n::tr};
 
 impl TempMailApp {
     pub(super) fn email_list(&mut self, ui: &mut egui::Ui) {
+        if self.settings.date_format == DateFormat::Relative {
+            ui.ctx().request_repaint_after(Duration::from_secs(30));
+        }
         ScrollArea::vertical().show(ui, |ui| {
-            if self.emails.is_empty() {
+            if self.inbox.emails.is_empty() {
                 ui.centered_and_justified(|ui| {
-                    ui.heading("No emails are here yet");
+                    ui.heading(tr("list_empty"));
                 });
                 return;
             }
-            for (index, email) in self.emails.clone().into_iter().enumerate() {
-                self.email_tile(ui, index, email);
+            for email in self.inbox.emails.clone() {
+                self.email_tile(ui, email);
             }
-            if self.email_count as usize > self.emails.len() && ui.button("Load more").clicked() {
-                self.send_event(Event::FetchMoreEmails(self.email(), self.current_offset));
-                self.current_offset += 1;
+            if self.inbox.email_count as usize > self.inbox.emails.len()
+                && ui.button(tr("list_load_more")).clicked()
+            {
+                self.send_event(Event::FetchMoreEmails(
+                    self.email(),
+                    self.inbox.current_offset,
+                ));
+                self.inbox.current_offset += 1;
             };
         });
     }
 }
 impl TempMailApp {
-    fn email_tile(&mut self, ui: &mut egui::Ui, index: usize, email: Email) {
+    fn email_tile(&mut self, ui: &mut egui::Ui, email: Email) {
         let Email {
             id,
             from_address,
@@ -35,7 +45,8 @@ impl TempMailApp {
             attachment_count,
             ..
         } = email;
-        let date = Self::get_date(received_at);
+        let date = self.settings.date_formatter().format(received_at);
+        let full_date = self.settings.date_formatter().full(received_at);
         Frame::new().stroke(Stroke::default()).show(ui, |ui| {
             egui_extras::StripBuilder::new(ui)
                 .size(egui_extras::Size::relative(0.9))
@@ -45,15 +56,23 @@ impl TempMailApp {
                     if has_attachments {
                         ui.label(format!("📎{attachment_count}"));
                     }
-                    self.trash_button(index, &id, ui);
-                    self.open_button(id, ui);
+                    self.trash_button(&id, ui);
+                    self.open_button(id.clone(), ui);
                 });
                 ui.vertical(|ui| {
                     ui.horizontal(|ui| {
+                        if !self.read.contains(&id) {
+                            ui.label("●").on_hover_text(tr("list_unread"));
+                        }
                         ui.heading(&subject);
+                        for label in self.labels.get(&id).into_iter().flatten() {
+                            ui.label(RichText::new(label).small().background_color(
+                                ui.visuals().selection.bg_fill.gamma_multiply(0.5),
+                            ));
+                        }
                     });
                     ui.small(from_address);
-                    ui.small(date);
+                    ui.small(date).on_hover_text(full_date);
                 });
             });
         });
@@ -66,9 +85,9 @@ impl TempMailApp {
         }
     }
 
-    fn trash_button(&mut self, index: usize, id: &str, ui: &mut egui::Ui) {
+    fn trash_button(&mut self, id: &str, ui: &mut egui::Ui) {
         if ui.small_button("🗑").clicked() {
-            self.send_event(Event::DeleteEmail(id.to_owned(), index));
+            self.send_event(Event::DeleteEmail(id.to_owned()));
         }
     }
 }
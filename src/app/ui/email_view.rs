@@ -1,13 +1,24 @@
-use crate::app::TempMailApp;
+use crate::app::{TempMailApp, actions::Action};
 
 use eframe::egui::{self, Frame, Margin, RichText, ScrollArea, Separator, Widget};
 use tmapi::Email;
 
-use crate::event_handler::Event;
+use tmgui::{
+    event_handler::Event,
+    i18n::tr,
+    inbox,
+    links::{self, Link},
+};
 
 pub mod attachment_list;
+pub mod body_text;
+pub mod card_list;
+pub mod link_list;
 impl TempMailApp {
     pub(super) fn email_view(&mut self, ui: &mut egui::Ui, email: Email) {
+        let code = inbox::verification_code(&email);
+        let link = inbox::verification_link(&email);
+        let links = links::links(&email);
         let Email {
             id,
             from_address,
@@ -20,7 +31,6 @@ impl TempMailApp {
             ..
         } = email;
         ScrollArea::vertical().show(ui, |ui| {
-            let date = TempMailApp::get_date(received_at);
             ui.vertical(|ui| {
                 ui.label(RichText::new(subject).size(40.).strong());
                 Frame::group(ui.style())
@@ -28,11 +38,17 @@ impl TempMailApp {
                     .corner_radius(ui.style().visuals.menu_corner_radius)
                     .show(ui, |ui| {
                         ui.set_width(ui.available_width());
-                        self.email_info(from_address, to_address, date, ui);
+                        self.email_info(from_address, to_address, received_at, ui);
+                        if self.action_enabled(&Action::ForwardEmail) {
+                            self.action_button(ui, Action::ForwardEmail);
+                        }
+                        self.extracted(ui, code, link);
                         Separator::default().spacing(20.).ui(ui);
-                        ui.horizontal_wrapped(|ui| ui.label(text_content.unwrap_or_default()));
+                        self.body_text(ui, &text_content.unwrap_or_default());
+                        self.link_list(ui, links);
 
-                        if let Some(attachments) = self.attachments.clone() {
+                        if let Some(attachments) = self.inbox.attachments.clone() {
+                            self.card_list(ui, &attachments);
                             self.attachment_list(ui, &attachments);
                         } else if has_attachments
                             && ui.small_button(format!("📎{attachment_count}",)).clicked()
@@ -49,12 +65,39 @@ impl TempMailApp {
         &self,
         from_address: String,
         to_address: String,
-        date: String,
+        received_at: i64,
         ui: &mut egui::Ui,
     ) {
-        ui.label(format!("{:10} {from_address}", "From"));
-        ui.label(format!("{:10} {to_address}", "To"));
+        ui.label(format!("{:10} {from_address}", tr("view_from")));
+        ui.label(format!("{:10} {to_address}", tr("view_to")));
         ui.spacing();
-        ui.small(date);
+        ui.small(self.settings.date_formatter().format(received_at))
+            .on_hover_text(self.settings.date_formatter().full(received_at));
+    }
+
+    fn extracted(&mut self, ui: &mut egui::Ui, code: Option<String>, link: Option<String>) {
+        if let Some(code) = code {
+            ui.horizontal(|ui| {
+                ui.label(format!("{:10}", tr("view_code")));
+                let text = RichText::new(&code).monospace().strong();
+                if ui.button(text).on_hover_text(tr("top_copy")).clicked() {
+                    ui.ctx().copy_text(code);
+                }
+            });
+        }
+        if let Some(link) = link {
+            ui.horizontal(|ui| {
+                ui.label(format!("{:10}", tr("view_link")));
+                let link = Link::new(link, None);
+                let warning = match link.warnings.is_empty() {
+                    true => "",
+                    false => "⚠ ",
+                };
+                let text = RichText::new(format!("{warning}{}", link.url)).small();
+                if ui.link(text).clicked() {
+                    self.confirm_link(link);
+                }
+            });
+        }
     }
 }
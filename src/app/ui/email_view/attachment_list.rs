@@ -1,8 +1,65 @@
-use eframe::egui::{self, Frame, Margin};
-use std::fmt::{Display, Write};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use eframe::egui::{self, Button, Frame, Grid, Margin, RichText, ScrollArea};
 use tmapi::Attachment;
+use tmgui::{
+    archives::{Entry, Kind},
+    attachments::{FileCheck, Risk, check_file, declared_risk, id_dir},
+    cards::{CardKind, Cards},
+    event_handler::Event,
+    i18n::{tr, tr_with},
+    inbox::safe_filename,
+    previews::Format,
+    units::UnitSize,
+};
+
+use crate::app::{TempMailApp, ui::preview_window::PreviewWindow};
+
+/// What to do with an attachment once the backend has downloaded it.
+pub enum Opening {
+    External,
+    Image(String),
+    /// Show in a viewer window with this title.
+    Preview(Format, String),
+    Save,
+    /// List the entries of an archive.
+    Archive(Kind),
+    /// Show the events or contacts in it.
+    Card(CardKind),
+}
+
+/// An attachment the backend is downloading.
+pub struct Download {
+    pub path: PathBuf,
+    pub id: String,
+    pub declared_risk: Option<Risk>,
+    pub opening: Opening,
+}
+
+/// A downloaded attachment.
+pub struct Downloaded {
+    pub path: PathBuf,
+    pub check: FileCheck,
+    /// Kept from opening because of its risk.
+    pub blocked: bool,
+}
 
-use crate::app::TempMailApp;
+/// The entries of an archive attachment.
+pub struct ArchiveView {
+    pub path: PathBuf,
+    pub kind: Kind,
+    pub entries: Vec<Entry>,
+    pub selected: HashSet<String>,
+    /// The previewed entry and its text, `None` if it is binary.
+    pub preview: Option<(String, Option<String>)>,
+    /// Where the selected entries were extracted to.
+    pub extracted: Option<PathBuf>,
+    /// Listing, previewing or extracting on a thread.
+    pub busy: bool,
+}
 
 impl TempMailApp {
     pub(super) fn attachment_list(&mut self, ui: &mut egui::Ui, attachments: &[Attachment]) {
@@ -18,46 +75,383 @@ impl TempMailApp {
 }
 
 impl TempMailApp {
+    /// Checks a downloaded attachment and opens it if it was requested for
+    /// viewing and looks safe.
+    pub(crate) fn attachment_saved(&mut self, path: &Path) {
+        let Some(index) = self.downloads.iter().position(|d| d.path == path) else {
+            return;
+        };
+        let download = self.downloads.remove(index);
+        let check = match check_file(path) {
+            Ok(check) => check,
+            Err(e) => {
+                let path = path.display().to_string();
+                let error = e.to_string();
+                self.error = Some(tr_with(
+                    "attachment_check_failed",
+                    &[("path", &path), ("error", &error)],
+                ));
+                return;
+            }
+        };
+        let risky = download.declared_risk.is_some() || check.risk.is_some();
+        let blocked = risky
+            && matches!(
+                download.opening,
+                Opening::External | Opening::Image(_) | Opening::Preview(..)
+            );
+        match download.opening {
+            Opening::External if !risky => {
+                open::that(path).ok();
+            }
+            Opening::Image(name) if !risky => {
+                let view_info = (format!("file://{}", path.display()), name);
+                if !self.images.contains(&view_info) {
+                    self.images.push(view_info);
+                }
+            }
+            Opening::Preview(format, name) if !risky => {
+                self.previews.retain(|window| window.path != path);
+                self.previews.push(PreviewWindow {
+                    name,
+                    path: path.to_owned(),
+                    preview: None,
+                    raw: false,
+                    pretty: None,
+                });
+                self.files.load(path.to_owned(), format);
+            }
+            Opening::Card(kind) => match std::fs::read(path) {
+                Ok(bytes) => {
+                    let cards = Cards::parse(kind, &String::from_utf8_lossy(&bytes));
+                    self.cards.insert(download.id.clone(), cards);
+                }
+                Err(e) => {
+                    let (path, error) = (path.display().to_string(), e.to_string());
+                    self.error = Some(tr_with(
+                        "attachment_read_failed",
+                        &[("path", &path), ("error", &error)],
+                    ));
+                }
+            },
+            Opening::Archive(kind) => {
+                let view = ArchiveView {
+                    path: path.to_owned(),
+                    kind,
+                    entries: Vec::new(),
+                    selected: HashSet::new(),
+                    preview: None,
+                    extracted: None,
+                    busy: true,
+                };
+                self.archives.insert(download.id.clone(), view);
+                self.files.list(download.id.clone(), path.to_owned(), kind);
+            }
+            _ => {}
+        }
+        let downloaded = Downloaded {
+            path: path.to_owned(),
+            check,
+            blocked,
+        };
+        self.downloaded.insert(download.id, downloaded);
+    }
+
+    /// The download limit in bytes for an attachment, 0 once the user allowed
+    /// it to be larger.
+    fn download_limit(&self, id: &str) -> u64 {
+        match self.oversize_allowed.contains(id) {
+            true => 0,
+            false => self.settings.max_download_bytes(),
+        }
+    }
+
+    fn too_large(&self, id: &str, size: u64) -> bool {
+        let limit = self.download_limit(id);
+        limit > 0 && size > limit
+    }
+
+    /// The backend stops a download that grows past the limit, as the size
+    /// the server lists may be wrong.
+    fn download(&mut self, attachment: &Attachment, path: PathBuf, opening: Opening) {
+        self.downloads.push(Download {
+            path: path.clone(),
+            id: attachment.id.clone(),
+            declared_risk: declared_risk(attachment),
+            opening,
+        });
+        let limit = self.download_limit(&attachment.id);
+        self.send_event(Event::DownloadAttachment(
+            attachment.id.clone(),
+            path,
+            limit,
+        ));
+    }
+
+    /// Downloads into the cache directory, so it goes through the backend
+    /// and its proxy.
+    pub(super) fn open_attachment(&mut self, attachment: &Attachment, opening: Opening) {
+        let dir = match self.cache_dir() {
+            Ok(cache) => cache.join(id_dir(&attachment.id)),
+            Err(e) => {
+                let error = e.to_string();
+                self.error = Some(tr_with("attachment_cache_failed", &[("error", &error)]));
+                return;
+            }
+        };
+        if let Err(e) = std::fs::create_dir_all(&dir) {
+            let (path, error) = (dir.display().to_string(), e.to_string());
+            self.error = Some(tr_with(
+                "attachment_dir_failed",
+                &[("path", &path), ("error", &error)],
+            ));
+            return;
+        }
+        self.download(attachment, dir.join(safe_filename(attachment)), opening);
+    }
+
     fn attachment_tile(&mut self, ui: &mut egui::Ui, attachment: &Attachment) {
+        let risk = declared_risk(attachment);
+        let too_large = self.too_large(&attachment.id, attachment.size);
         Frame::group(ui.style())
             .inner_margin(Margin::symmetric(5, 5))
             .corner_radius(ui.style().visuals.menu_corner_radius)
             .show(ui, |ui| {
                 let file_ext = attachment.filename.split('.').next_back();
                 let is_img = ["jpg", "png"].contains(&file_ext.unwrap_or_default());
-                let file_icon = if is_img { "🖼" } else { "📄" };
+                let file_icon = match CardKind::of(attachment) {
+                    _ if is_img => "🖼",
+                    Some(CardKind::Calendar) => "📅",
+                    Some(CardKind::Contact) => "👤",
+                    None => "📄",
+                };
                 ui.horizontal(|ui| {
                     ui.label(format!("{}{}", file_icon, attachment.filename));
                     ui.separator();
                     ui.label(UnitSize(attachment.size).to_string());
                     ui.separator();
-                    let api_url = "https://api.barid.site";
-                    let path = format!("{api_url}/attachments/{}", attachment.id);
-                    if ui.button("↓").clicked() {
-                        open::that(&path).ok();
+                    let open_hint = match risk {
+                        Some(_) => tr("attachment_not_opened"),
+                        None => tr("attachment_open_external"),
+                    };
+                    let button = ui
+                        .add_enabled(!too_large, Button::new("↓"))
+                        .on_hover_text(open_hint);
+                    button.context_menu(|ui| {
+                        if ui.button(tr("attachment_copy_link")).clicked() {
+                            ui.ctx()
+                                .copy_text(self.settings.attachment_url(&attachment.id));
+                        }
+                    });
+                    if button.clicked() {
+                        self.open_attachment(attachment, Opening::External);
                     }
-                    let view_info = (path, attachment.filename.clone());
-                    if is_img && ui.button("open").clicked() && !self.images.contains(&view_info) {
-                        self.images.push(view_info);
+                    if ui
+                        .add_enabled(!too_large, Button::new("💾"))
+                        .on_hover_text(tr("attachment_save"))
+                        .clicked()
+                    {
+                        let target = self.download_dir().join(safe_filename(attachment));
+                        self.download(attachment, target, Opening::Save);
                     }
-                })
+                    let format = Format::of(attachment);
+                    if (is_img || format.is_some())
+                        && risk.is_none()
+                        && ui
+                            .add_enabled(!too_large, Button::new(tr("attachment_open")))
+                            .on_hover_text(tr("attachment_open_hint"))
+                            .clicked()
+                    {
+                        let name = attachment.filename.clone();
+                        let opening = match format {
+                            Some(format) if !is_img => Opening::Preview(format, name),
+                            _ => Opening::Image(name),
+                        };
+                        self.open_attachment(attachment, opening);
+                    }
+                    if let Some(kind) = Kind::of(&attachment.filename) {
+                        let open = self.archives.contains_key(&attachment.id);
+                        let label = match open {
+                            true => tr("attachment_archive_close"),
+                            false => tr("attachment_archive_inspect"),
+                        };
+                        if ui.add_enabled(!too_large, Button::new(label)).clicked() {
+                            match open {
+                                true => drop(self.archives.remove(&attachment.id)),
+                                false => self.open_attachment(attachment, Opening::Archive(kind)),
+                            }
+                        }
+                    }
+                });
+                if let Some(risk) = risk {
+                    ui.colored_label(ui.visuals().warn_fg_color, format!("⚠ {}", risk.describe()));
+                }
+                if too_large {
+                    self.oversize_notice(ui, attachment);
+                }
+                self.download_check(ui, &attachment.id, risk);
+                self.archive_view(ui, &attachment.id);
             });
     }
-}
 
-struct UnitSize(pub u64);
-impl Display for UnitSize {
-    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let val = self.0 as f64;
-        const KILO: f64 = 1024.;
-        const MEGA: f64 = KILO * KILO;
-        const GIGA: f64 = KILO * MEGA;
-        match val {
-            0. => fmt.write_char('0'),
-            val if val < KILO => write!(fmt, "{}B", val as u64),
-            val if val < MEGA => write!(fmt, "{:.2}KB", val / KILO),
-            val if val < GIGA => write!(fmt, "{:.2}MB", val / MEGA),
-            val => write!(fmt, "{:.2}GB", val / GIGA),
+    fn archive_view(&mut self, ui: &mut egui::Ui, id: &str) {
+        let download_dir = self.download_dir();
+        let Some(view) = self.archives.get_mut(id) else {
+            return;
+        };
+        let mut preview = None;
+        let mut extract = None;
+        ui.separator();
+        if view.busy && view.entries.is_empty() {
+            ui.spinner();
+            return;
+        }
+        ScrollArea::vertical()
+            .id_salt(("archive", id))
+            .max_height(200.0)
+            .show(ui, |ui| {
+                Grid::new(("archive_entries", id))
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.label(tr("attachment_archive_name"));
+                        ui.label(tr("attachment_archive_size"));
+                        ui.label(tr("attachment_archive_ratio"));
+                        ui.end_row();
+                        for entry in &view.entries {
+                            if entry.is_dir {
+                                ui.label(format!("📁 {}", entry.name));
+                            } else {
+                                let mut checked = view.selected.contains(&entry.name);
+                                if ui.checkbox(&mut checked, &entry.name).changed() {
+                                    match checked {
+                                        true => view.selected.insert(entry.name.clone()),
+                                        false => view.selected.remove(&entry.name),
+                                    };
+                                }
+                            }
+                            ui.label(UnitSize(entry.size).to_string());
+                            match entry.ratio() {
+                                Some(ratio) => ui.label(format!("{:.0}%", ratio * 100.0)),
+                                None => ui.label("-"),
+                            };
+                            if !entry.is_dir
+                                && ui
+                                    .add_enabled(!view.busy, Button::new("👁").small())
+                                    .on_hover_text(tr("attachment_archive_preview"))
+                                    .clicked()
+                            {
+                                preview = Some(entry.name.clone());
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+        ui.horizontal(|ui| {
+            let count = view.selected.len().to_string();
+            let button = Button::new(tr_with("attachment_archive_extract", &[("count", &count)]));
+            if ui
+                .add_enabled(!view.busy && !view.selected.is_empty(), button)
+                .clicked()
+            {
+                let stem = view.path.file_stem().unwrap_or_default();
+                let mut names = view.selected.iter().cloned().collect::<Vec<_>>();
+                names.sort();
+                extract = Some((names, download_dir.join(stem)));
+            }
+            if view.busy {
+                ui.spinner();
+            }
+            if let Some(dir) = &view.extracted {
+                ui.small(tr_with(
+                    "attachment_archive_extracted",
+                    &[("path", &dir.display().to_string())],
+                ));
+                if ui.small_button(tr("attachment_show_folder")).clicked() {
+                    open::that(dir).ok();
+                }
+            }
+        });
+        let mut close = false;
+        if let Some((name, text)) = &view.preview {
+            ui.horizontal(|ui| {
+                ui.strong(name);
+                close = ui.small_button("🗙").clicked();
+            });
+            match text {
+                Some(text) => {
+                    ScrollArea::both()
+                        .id_salt(("archive_preview", id))
+                        .max_height(300.0)
+                        .show(ui, |ui| ui.label(RichText::new(text).monospace()));
+                }
+                None => {
+                    ui.small(tr("attachment_archive_binary"));
+                }
+            }
+        }
+        if close {
+            view.preview = None;
+        }
+        let (path, kind) = (view.path.clone(), view.kind);
+        if let Some(name) = preview {
+            view.busy = true;
+            self.files.preview(id.to_owned(), path.clone(), kind, name);
+        }
+        if let Some((names, dir)) = extract {
+            view.busy = true;
+            self.files.extract(id.to_owned(), path, kind, names, dir);
+        }
+    }
+
+    fn oversize_notice(&mut self, ui: &mut egui::Ui, attachment: &Attachment) {
+        ui.horizontal(|ui| {
+            let limit = self.settings.max_download_mb.to_string();
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                tr_with("attachment_too_large", &[("limit", &limit)]),
+            );
+            if ui.button(tr("attachment_allow_large")).clicked() {
+                self.oversize_allowed.insert(attachment.id.clone());
+            }
+        });
+    }
+
+    /// The hash of a downloaded attachment and what its content revealed.
+    fn download_check(&self, ui: &mut egui::Ui, id: &str, declared: Option<Risk>) {
+        let Some(downloaded) = self.downloaded.get(id) else {
+            return;
+        };
+        let check = &downloaded.check;
+        if let Some(risk) = check.risk
+            && declared != Some(risk)
+        {
+            let text = tr_with("attachment_content_risk", &[("risk", &risk.describe())]);
+            ui.colored_label(ui.visuals().warn_fg_color, format!("⚠ {text}"));
+        }
+        ui.horizontal(|ui| {
+            ui.small(tr("attachment_sha256"));
+            ui.label(RichText::new(&check.sha256[..16]).monospace().small())
+                .on_hover_text(&check.sha256);
+            if ui
+                .small_button("📋")
+                .on_hover_text(tr("top_copy"))
+                .clicked()
+            {
+                ui.ctx().copy_text(check.sha256.clone());
+            }
+        });
+        if downloaded.blocked {
+            ui.horizontal(|ui| {
+                ui.small(tr_with(
+                    "attachment_blocked",
+                    &[("path", &downloaded.path.display().to_string())],
+                ));
+                if let Some(dir) = downloaded.path.parent()
+                    && ui.small_button(tr("attachment_show_folder")).clicked()
+                {
+                    open::that(dir).ok();
+                }
+            });
         }
     }
 }
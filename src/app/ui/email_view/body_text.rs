@@ -0,0 +1,80 @@
+use std::ops::Range;
+
+use eframe::egui::{self, Align, Color32, FontId, Id, Layout, RichText};
+use unicode_bidi::BidiInfo;
+
+use crate::app::TempMailApp;
+
+impl TempMailApp {
+    pub(super) fn body_text(&self, ui: &mut egui::Ui, text: &str) {
+        let font = self.settings.email_font_id();
+        if !BidiInfo::new(text, None).has_rtl() {
+            ui.horizontal_wrapped(|ui| ui.label(RichText::new(text).font(font)));
+            return;
+        }
+        for paragraph in text.lines() {
+            bidi_paragraph(ui, paragraph, &font);
+        }
+    }
+}
+
+// egui lays text out left to right only, so paragraphs containing right-to-left
+// text are wrapped in logical order and each line is reordered for display.
+fn bidi_paragraph(ui: &mut egui::Ui, paragraph: &str, font: &FontId) {
+    let bidi = BidiInfo::new(paragraph, None);
+    let Some(info) = bidi.paragraphs.first() else {
+        ui.label(RichText::new(" ").font(font.clone()));
+        return;
+    };
+    let layout = if info.level.is_rtl() {
+        Layout::right_to_left(Align::TOP)
+    } else {
+        Layout::left_to_right(Align::TOP)
+    };
+    for line in wrap_lines(ui, paragraph, font) {
+        let visual = bidi.reorder_line(info, line).into_owned();
+        ui.with_layout(layout, |ui| {
+            ui.label(RichText::new(visual).font(font.clone()))
+        });
+    }
+}
+
+/// Breaks at spaces, measuring each word once. The lines are kept for the
+/// paragraph, width and font, as they are needed every frame.
+fn wrap_lines(ui: &egui::Ui, paragraph: &str, font: &FontId) -> Vec<Range<usize>> {
+    let max_width = ui.available_width();
+    let id = Id::new(("bidi_lines", paragraph, max_width.to_bits(), font));
+    if let Some(lines) = ui.data(|data| data.get_temp(id)) {
+        return lines;
+    }
+    let lines = ui.fonts(|fonts| {
+        let width = |text: &str| {
+            fonts
+                .layout_no_wrap(text.to_owned(), font.clone(), Color32::PLACEHOLDER)
+                .size()
+                .x
+        };
+        let space = width(" ");
+        let mut lines = Vec::new();
+        let mut start = 0;
+        let mut line_width = 0.;
+        let mut offset = 0;
+        for word in paragraph.split(' ') {
+            let word_width = width(word);
+            if offset == start {
+                line_width = word_width;
+            } else if line_width + space + word_width > max_width {
+                lines.push(start..offset - 1);
+                start = offset;
+                line_width = word_width;
+            } else {
+                line_width += space + word_width;
+            }
+            offset += word.len() + 1;
+        }
+        lines.push(start..paragraph.len());
+        lines
+    });
+    ui.data_mut(|data| data.insert_temp(id, lines.clone()));
+    lines
+}
@@ -0,0 +1,161 @@
+use eframe::egui::{self, CollapsingHeader, Frame, Grid, Margin, RichText};
+use tmapi::Attachment;
+use tmgui::{
+    cards::{self, CalendarEvent, CardKind, Cards, Contact, describe_recurrence},
+    i18n::{tr, tr_with},
+};
+
+use crate::app::{TempMailApp, ui::email_view::attachment_list::Opening};
+
+/// Larger calendar and contact files are left as plain attachments.
+const MAX_CARD_SIZE: u64 = 1024 * 1024;
+
+impl TempMailApp {
+    /// Downloads the calendar and contact attachments of the viewed email to
+    /// show them as cards.
+    pub(crate) fn load_cards(&mut self, attachments: &[Attachment]) {
+        for attachment in attachments {
+            if let Some(kind) = CardKind::of(attachment)
+                && attachment.size <= MAX_CARD_SIZE
+                && !self.cards.contains_key(&attachment.id)
+            {
+                self.open_attachment(attachment, Opening::Card(kind));
+            }
+        }
+    }
+
+    pub(super) fn card_list(&mut self, ui: &mut egui::Ui, attachments: &[Attachment]) {
+        for attachment in attachments {
+            let Some(cards) = self.cards.remove(&attachment.id) else {
+                continue;
+            };
+            match &cards {
+                Cards::Events(events) => {
+                    for event in events {
+                        self.event_card(ui, &attachment.id, event);
+                    }
+                }
+                Cards::Contacts(contacts) => {
+                    for contact in contacts {
+                        self.contact_card(ui, contact);
+                    }
+                }
+            }
+            self.cards.insert(attachment.id.clone(), cards);
+        }
+        if let Some(path) = &self.card_saved {
+            ui.small(tr_with(
+                "card_saved",
+                &[("path", &path.display().to_string())],
+            ));
+        }
+    }
+
+    fn event_card(&mut self, ui: &mut egui::Ui, id: &str, event: &CalendarEvent) {
+        let dates = self.settings.date_formatter();
+        let mut export = false;
+        card_frame(ui, |ui| {
+            ui.horizontal(|ui| {
+                let summary = match event.summary.is_empty() {
+                    true => tr("card_untitled"),
+                    false => event.summary.clone(),
+                };
+                ui.label(RichText::new(format!("📅 {summary}")).strong());
+                if event.cancelled {
+                    ui.colored_label(ui.visuals().error_fg_color, tr("card_cancelled"));
+                }
+            });
+            Grid::new(("event", id, &event.summary))
+                .num_columns(2)
+                .show(ui, |ui| {
+                    if let Some(start) = &event.start {
+                        row(ui, tr("card_start"), start.format(&dates));
+                    }
+                    if let Some(end) = &event.end {
+                        row(ui, tr("card_end"), end.format(&dates));
+                    }
+                    if let Some(location) = &event.location {
+                        row(ui, tr("card_location"), location.clone());
+                    }
+                    if let Some(organizer) = &event.organizer {
+                        row(ui, tr("card_organizer"), organizer.clone());
+                    }
+                    if let Some(rule) = &event.recurrence {
+                        row(ui, tr("card_recurrence"), describe_recurrence(rule));
+                    }
+                });
+            if let Some(description) = &event.description {
+                CollapsingHeader::new(tr("card_description"))
+                    .id_salt(("event_description", id, &event.summary))
+                    .show(ui, |ui| ui.label(description));
+            }
+            ui.horizontal(|ui| {
+                if let Some(downloaded) = self.downloaded.get(id)
+                    && ui.button(tr("card_add_calendar")).clicked()
+                {
+                    open::that(&downloaded.path).ok();
+                }
+                export = ui.button(tr("card_export_ics")).clicked();
+                if ui.button(tr("top_copy")).clicked() {
+                    ui.ctx().copy_text(event.details(&dates));
+                }
+            });
+        });
+        if export {
+            self.export_card(&cards::export_name(&event.summary, "ics"), &event.source);
+        }
+    }
+
+    fn contact_card(&mut self, ui: &mut egui::Ui, contact: &Contact) {
+        card_frame(ui, |ui| {
+            ui.label(RichText::new(format!("👤 {}", contact.name)).strong());
+            if let Some(organization) = &contact.organization {
+                ui.small(organization);
+            }
+            for value in contact.emails.iter().chain(&contact.phones) {
+                if ui.link(value).on_hover_text(tr("top_copy")).clicked() {
+                    ui.ctx().copy_text(value.clone());
+                }
+            }
+            ui.horizontal(|ui| {
+                if ui.button(tr("card_export_vcf")).clicked() {
+                    self.export_card(&cards::export_name(&contact.name, "vcf"), &contact.source);
+                }
+                if ui.button(tr("top_copy")).clicked() {
+                    ui.ctx().copy_text(contact.details());
+                }
+            });
+        });
+    }
+
+    fn export_card(&mut self, filename: &str, source: &str) {
+        let dir = self.download_dir();
+        let path = dir.join(filename);
+        match std::fs::create_dir_all(&dir).and_then(|()| std::fs::write(&path, source)) {
+            Ok(()) => self.card_saved = Some(path),
+            Err(e) => {
+                let (path, error) = (path.display().to_string(), e.to_string());
+                self.error = Some(tr_with(
+                    "card_save_failed",
+                    &[("path", &path), ("error", &error)],
+                ));
+            }
+        }
+    }
+}
+
+fn card_frame(ui: &mut egui::Ui, add_contents: impl FnOnce(&mut egui::Ui)) {
+    Frame::group(ui.style())
+        .inner_margin(Margin::symmetric(8, 8))
+        .corner_radius(ui.style().visuals.menu_corner_radius)
+        .show(ui, |ui| {
+            ui.set_width(ui.available_width());
+            add_contents(ui);
+        });
+}
+
+fn row(ui: &mut egui::Ui, label: String, value: String) {
+    ui.label(label);
+    ui.label(value);
+    ui.end_row();
+}
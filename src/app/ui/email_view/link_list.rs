@@ -0,0 +1,47 @@
+use eframe::egui::{self, CollapsingHeader, RichText};
+use tmgui::{i18n::tr_with, links::Link};
+
+use crate::app::TempMailApp;
+
+impl TempMailApp {
+    pub(super) fn link_list(&mut self, ui: &mut egui::Ui, links: Vec<Link>) {
+        if links.is_empty() {
+            return;
+        }
+        let count = links.len().to_string();
+        let suspicious = links
+            .iter()
+            .filter(|link| !link.warnings.is_empty())
+            .count();
+        let title = match suspicious {
+            0 => tr_with("view_links", &[("count", &count)]),
+            _ => tr_with(
+                "view_links_suspicious",
+                &[("count", &count), ("suspicious", &suspicious.to_string())],
+            ),
+        };
+        let mut clicked = None;
+        CollapsingHeader::new(title)
+            .id_salt("links")
+            .show(ui, |ui| {
+                for (index, link) in links.iter().enumerate() {
+                    ui.horizontal_wrapped(|ui| {
+                        if !link.warnings.is_empty() {
+                            let warnings = link.warnings.iter().map(|w| w.describe());
+                            ui.colored_label(ui.visuals().warn_fg_color, "⚠")
+                                .on_hover_text(warnings.collect::<Vec<_>>().join("\n"));
+                        }
+                        if let Some(text) = &link.text {
+                            ui.label(RichText::new(text).strong());
+                        }
+                        if ui.link(RichText::new(&link.url).small()).clicked() {
+                            clicked = Some(index);
+                        }
+                    });
+                }
+            });
+        if let Some(link) = clicked.and_then(|index| links.into_iter().nth(index)) {
+            self.confirm_link(link);
+        }
+    }
+}
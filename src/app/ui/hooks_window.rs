@@ -0,0 +1,79 @@
+use eframe::egui::{self, Button, Grid, ScrollArea, Spinner, Window};
+use tmgui::i18n::{tr, tr_with};
+
+use crate::app::TempMailApp;
+
+impl TempMailApp {
+    pub(super) fn hooks_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.hooks_open;
+        Window::new(tr("hooks_title"))
+            .open(&mut open)
+            .default_width(480.)
+            .show(ctx, |ui| {
+                Grid::new("hooks_grid").num_columns(2).show(ui, |ui| {
+                    ui.label(tr("hooks_command"));
+                    ui.text_edit_singleline(&mut self.settings.hook_command)
+                        .on_hover_text(tr("hooks_command_hint"));
+                    ui.end_row();
+
+                    ui.label(tr("hooks_url"));
+                    ui.text_edit_singleline(&mut self.settings.hook_url)
+                        .on_hover_text(tr("hooks_url_hint"));
+                    ui.end_row();
+                });
+                ui.horizontal(|ui| {
+                    let newest = self.inbox.emails.first().cloned();
+                    let test = Button::new(tr("hooks_test"));
+                    let enabled = newest.is_some() && self.hooks_configured();
+                    if ui
+                        .add_enabled(enabled, test)
+                        .on_hover_text(tr("hooks_test_hint"))
+                        .clicked()
+                        && let Some(email) = newest
+                    {
+                        self.run_hooks(vec![email]);
+                    }
+                    if ui.button(tr("hooks_clear")).clicked() {
+                        self.hook_log.clear();
+                    }
+                    if self.hooks_running > 0 {
+                        ui.add(Spinner::new());
+                    }
+                });
+                ui.separator();
+                self.hook_log(ui);
+            });
+        self.hooks_open = open;
+    }
+
+    fn hook_log(&self, ui: &mut egui::Ui) {
+        if self.hook_log.is_empty() {
+            ui.label(tr("hooks_log_empty"));
+            return;
+        }
+        let dates = self.settings.date_formatter();
+        ScrollArea::vertical()
+            .id_salt("hook_log")
+            .max_height(300.)
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                Grid::new("hook_log_grid").striped(true).show(ui, |ui| {
+                    for log in &self.hook_log {
+                        ui.label(dates.format(log.at))
+                            .on_hover_text(dates.full(log.at));
+                        ui.label(&log.subject);
+                        ui.label(&log.target);
+                        let attempts = log.attempts.to_string();
+                        let attempts = tr_with("hooks_attempts", &[("count", &attempts)]);
+                        match &log.result {
+                            Ok(()) => ui.label(format!("✔ {attempts}")),
+                            Err(e) => ui
+                                .colored_label(ui.visuals().error_fg_color, format!("✖ {attempts}"))
+                                .on_hover_text(e),
+                        };
+                        ui.end_row();
+                    }
+                });
+            });
+    }
+}
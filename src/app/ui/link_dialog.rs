@@ -0,0 +1,65 @@
+use eframe::egui::{self, Id, Key, Label, Modal, RichText};
+use tmgui::{
+    i18n::tr,
+    links::{Link, strip_tracking},
+};
+
+use crate::app::TempMailApp;
+
+impl TempMailApp {
+    /// Asks before opening a link from an email.
+    pub(crate) fn confirm_link(&mut self, link: Link) {
+        self.pending_link = Some(link);
+    }
+
+    pub(super) fn link_dialog(&mut self, ctx: &egui::Context) {
+        let Some(link) = &self.pending_link else {
+            return;
+        };
+        let stripped = strip_tracking(&link.url);
+        let url = match self.settings.strip_tracking {
+            true => stripped.clone(),
+            false => link.url.clone(),
+        };
+        let mut open = false;
+        let mut close = false;
+        let modal = Modal::new(Id::new("link_dialog")).show(ctx, |ui| {
+            ui.set_max_width(480.);
+            ui.heading(tr("link_title"));
+            if let Some(host) = link.host() {
+                ui.label(RichText::new(host).strong().size(20.));
+            }
+            ui.add(Label::new(RichText::new(&url).monospace()).wrap());
+            if let Some(text) = &link.text {
+                ui.small(format!("{} {text}", tr("link_shown_as")));
+            }
+            for warning in &link.warnings {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    format!("⚠ {}", warning.describe()),
+                );
+            }
+            if stripped != link.url {
+                ui.checkbox(&mut self.settings.strip_tracking, tr("link_strip_tracking"));
+            }
+            ui.separator();
+            ui.horizontal(|ui| {
+                let label = match link.warnings.is_empty() {
+                    true => tr("link_open"),
+                    false => tr("link_open_anyway"),
+                };
+                open = ui.button(label).clicked();
+                if ui.button(tr("top_copy")).clicked() {
+                    ui.ctx().copy_text(url.clone());
+                }
+                close = ui.button(tr("link_cancel")).clicked();
+            });
+        });
+        if open {
+            open::that(&url).ok();
+        }
+        if open || close || modal.should_close() || ctx.input(|i| i.key_pressed(Key::Escape)) {
+            self.pending_link = None;
+        }
+    }
+}
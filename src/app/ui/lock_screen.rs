@@ -0,0 +1,30 @@
+use eframe::egui::{self, CentralPanel, Key, TextEdit};
+use tmgui::i18n::tr;
+
+use crate::app::{TempMailApp, actions::Action};
+
+impl TempMailApp {
+    pub(super) fn lock_screen(&mut self, ctx: &egui::Context) {
+        CentralPanel::default().show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.add_space(ui.available_height() / 3.);
+                ui.heading(tr("lock_title"));
+                let field = ui.add(
+                    TextEdit::singleline(&mut self.passphrase)
+                        .password(true)
+                        .hint_text(tr("lock_passphrase")),
+                );
+                field.request_focus();
+                let entered = field.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
+                if ui.button(tr("lock_unlock")).clicked() || entered {
+                    self.unlock();
+                }
+                if let Some(error) = &self.error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+                ui.add_space(20.);
+                self.action_button(ui, Action::Panic);
+            });
+        });
+    }
+}
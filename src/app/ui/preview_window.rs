@@ -0,0 +1,151 @@
+use std::path::PathBuf;
+
+use eframe::egui::{self, CollapsingHeader, Id, RichText, ScrollArea, Window};
+use egui_extras::{Column, TableBuilder};
+use serde_json::Value;
+use tmgui::{
+    i18n::{tr, tr_with},
+    previews::{MAX_ROWS, Preview},
+};
+
+use crate::app::TempMailApp;
+
+/// A window showing a downloaded attachment.
+pub struct PreviewWindow {
+    pub name: String,
+    pub path: PathBuf,
+    /// `None` while it is read.
+    pub preview: Option<Preview>,
+    /// Shows JSON as indented text instead of a tree.
+    pub raw: bool,
+    /// The indented JSON, made when it is first shown.
+    pub pretty: Option<String>,
+}
+
+impl TempMailApp {
+    pub(super) fn preview_windows(&mut self, ctx: &egui::Context) {
+        let mut closed = None;
+        for (index, window) in self.previews.iter_mut().enumerate() {
+            let mut open = true;
+            Window::new(&window.name)
+                .id(Id::new(("preview", &window.path)))
+                .open(&mut open)
+                .default_size([640., 480.])
+                .show(ctx, |ui| preview(ui, window));
+            if !open {
+                closed = Some(index);
+            }
+        }
+        if let Some(index) = closed {
+            self.previews.remove(index);
+        }
+    }
+}
+
+fn preview(ui: &mut egui::Ui, window: &mut PreviewWindow) {
+    let Some(preview) = &window.preview else {
+        ui.spinner();
+        return;
+    };
+    match preview {
+        Preview::Text(text) => {
+            if ui.small_button(tr("top_copy")).clicked() {
+                ui.ctx().copy_text(text.clone());
+            }
+            ScrollArea::both().show(ui, |ui| ui.label(RichText::new(text).monospace()));
+        }
+        Preview::Table(rows) => table(ui, rows),
+        Preview::Json(value) => {
+            ui.checkbox(&mut window.raw, tr("preview_json_raw"));
+            ScrollArea::both().show(ui, |ui| match window.raw {
+                true => {
+                    let text = window.pretty.get_or_insert_with(|| {
+                        serde_json::to_string_pretty(value).unwrap_or_default()
+                    });
+                    ui.label(RichText::new(text.as_str()).monospace());
+                }
+                false => json_tree(ui, "$".into(), value, 0),
+            });
+        }
+        Preview::Pdf(pages) => {
+            ui.small(tr("preview_pdf_text_only"));
+            ScrollArea::vertical().show(ui, |ui| {
+                for (index, text) in pages.iter().enumerate() {
+                    let page = (index + 1).to_string();
+                    ui.strong(tr_with("preview_pdf_page", &[("page", &page)]));
+                    match text.trim().is_empty() {
+                        true => ui.weak(tr("preview_pdf_no_text")),
+                        false => ui.label(text.trim_end()),
+                    };
+                    ui.separator();
+                }
+            });
+        }
+    }
+}
+
+fn table(ui: &mut egui::Ui, rows: &[Vec<String>]) {
+    let Some((header, records)) = rows.split_first() else {
+        ui.weak(tr("preview_empty"));
+        return;
+    };
+    if rows.len() == MAX_ROWS {
+        let limit = MAX_ROWS.to_string();
+        ui.small(tr_with("preview_rows_limited", &[("count", &limit)]));
+    }
+    let columns = rows.iter().map(Vec::len).max().unwrap_or_default();
+    ScrollArea::horizontal().show(ui, |ui| {
+        TableBuilder::new(ui)
+            .striped(true)
+            .columns(Column::auto().resizable(true).clip(true), columns)
+            .header(20., |mut row| {
+                for title in header {
+                    row.col(|ui| {
+                        ui.strong(title);
+                    });
+                }
+            })
+            .body(|body| {
+                body.rows(18., records.len(), |mut row| {
+                    for cell in &records[row.index()] {
+                        row.col(|ui| {
+                            ui.label(cell);
+                        });
+                    }
+                });
+            });
+    });
+}
+
+/// Objects and arrays fold, the first levels start open.
+fn json_tree(ui: &mut egui::Ui, key: String, value: &Value, depth: usize) {
+    let (children, summary) = match value {
+        Value::Object(map) => (
+            map.iter().map(|(k, v)| (k.clone(), v)).collect::<Vec<_>>(),
+            format!("{{{}}}", map.len()),
+        ),
+        Value::Array(items) => (
+            items
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (format!("[{i}]"), v))
+                .collect(),
+            format!("[{}]", items.len()),
+        ),
+        leaf => {
+            ui.horizontal(|ui| {
+                ui.label(RichText::new(format!("{key}:")).monospace().strong());
+                ui.label(RichText::new(leaf.to_string()).monospace());
+            });
+            return;
+        }
+    };
+    CollapsingHeader::new(RichText::new(format!("{key} {summary}")).monospace())
+        .id_salt(ui.id().with((&key, depth)))
+        .default_open(depth < 2)
+        .show(ui, |ui| {
+            for (key, value) in children {
+                json_tree(ui, key, value, depth + 1);
+            }
+        });
+}
@@ -0,0 +1,162 @@
+use eframe::egui::{self, Button, CollapsingHeader, ComboBox, Grid, ScrollArea, Spinner, Window};
+use tmgui::{
+    i18n::tr,
+    rules::{AttachmentCondition, Rule, RuleAction},
+};
+
+use crate::app::TempMailApp;
+
+impl TempMailApp {
+    pub(super) fn rules_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.rules_open;
+        Window::new(tr("rules_title"))
+            .open(&mut open)
+            .default_width(420.)
+            .show(ctx, |ui| {
+                ScrollArea::vertical()
+                    .id_salt("rules")
+                    .max_height(400.)
+                    .show(ui, |ui| {
+                        let mut removed = None;
+                        for (index, rule) in self.settings.rules.iter_mut().enumerate() {
+                            let title = match rule.name.is_empty() {
+                                true => tr("rules_unnamed"),
+                                false => rule.name.clone(),
+                            };
+                            CollapsingHeader::new(title)
+                                .id_salt(("rule", index))
+                                .show(ui, |ui| {
+                                    if rule_editor(ui, index, rule) {
+                                        removed = Some(index);
+                                    }
+                                });
+                        }
+                        if let Some(index) = removed {
+                            self.settings.rules.remove(index);
+                        }
+                    });
+                ui.horizontal(|ui| {
+                    if ui.button(tr("rules_add")).clicked() {
+                        self.settings.rules.push(Rule::default());
+                    }
+                    let preview = Button::new(tr("rules_preview"));
+                    if ui
+                        .add_enabled(!self.inbox.emails.is_empty(), preview)
+                        .on_hover_text(tr("rules_preview_hint"))
+                        .clicked()
+                    {
+                        self.rule_preview = None;
+                        self.run_rules(self.inbox.emails.clone(), true);
+                    }
+                    if self.rules_running > 0 {
+                        ui.add(Spinner::new());
+                    }
+                });
+                self.rule_preview(ui);
+            });
+        self.rules_open = open;
+    }
+
+    fn rule_preview(&mut self, ui: &mut egui::Ui) {
+        let Some(outcomes) = &self.rule_preview else {
+            return;
+        };
+        ui.separator();
+        if outcomes.is_empty() {
+            ui.label(tr("rules_preview_empty"));
+            return;
+        }
+        ScrollArea::vertical()
+            .id_salt("rule_preview")
+            .max_height(200.)
+            .show(ui, |ui| {
+                Grid::new("rule_preview_grid").striped(true).show(ui, |ui| {
+                    for outcome in outcomes {
+                        ui.label(&outcome.email.subject);
+                        ui.label(&outcome.rule);
+                        let actions = outcome.actions.iter().map(RuleAction::name);
+                        ui.label(actions.collect::<Vec<_>>().join(", "));
+                        ui.end_row();
+                    }
+                });
+            });
+    }
+}
+
+/// Edits a rule, returning whether it should be removed.
+fn rule_editor(ui: &mut egui::Ui, index: usize, rule: &mut Rule) -> bool {
+    Grid::new(("rule_grid", index))
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label(tr("rule_name"));
+            ui.text_edit_singleline(&mut rule.name);
+            ui.end_row();
+
+            ui.label(tr("rule_enabled"));
+            ui.checkbox(&mut rule.enabled, "");
+            ui.end_row();
+
+            for (label, pattern) in [
+                ("rule_from", &mut rule.from),
+                ("rule_subject", &mut rule.subject),
+                ("rule_body", &mut rule.body),
+            ] {
+                ui.label(tr(label));
+                ui.text_edit_singleline(pattern)
+                    .on_hover_text(tr("rule_pattern_hint"));
+                ui.end_row();
+            }
+
+            ui.label(tr("rule_attachments"));
+            ComboBox::from_id_salt(("rule_attachments", index))
+                .selected_text(rule.attachments.name())
+                .show_ui(ui, |ui| {
+                    for condition in AttachmentCondition::ALL {
+                        ui.selectable_value(&mut rule.attachments, condition, condition.name());
+                    }
+                });
+            ui.end_row();
+
+            ui.label(tr("rule_attachment_types"));
+            ui.text_edit_singleline(&mut rule.attachment_types)
+                .on_hover_text(tr("rule_attachment_types_hint"));
+            ui.end_row();
+        });
+    if let Err(e) = rule.filter() {
+        ui.colored_label(ui.visuals().error_fg_color, e);
+    }
+
+    ui.label(tr("rule_actions"));
+    let mut removed = None;
+    for (position, action) in rule.actions.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            match action {
+                RuleAction::Label(label) => {
+                    ui.label(tr("rule_action_label_empty"));
+                    ui.text_edit_singleline(label);
+                }
+                action => {
+                    ui.label(action.name());
+                }
+            }
+            if ui.small_button("✖").clicked() {
+                removed = Some(position);
+            }
+        });
+    }
+    if let Some(position) = removed {
+        rule.actions.remove(position);
+    }
+    let mut remove_rule = false;
+    ui.horizontal(|ui| {
+        ui.menu_button(tr("rule_add_action"), |ui| {
+            for action in RuleAction::all() {
+                if ui.button(action.name()).clicked() {
+                    rule.actions.push(action);
+                }
+            }
+        });
+        remove_rule = ui.button(tr("rules_remove")).clicked();
+    });
+    remove_rule
+}
@@ -0,0 +1,441 @@
+use crate::{
+    app::TempMailApp,
+    config::{Density, FontChoice, Settings, ThemeChoice, state::WindowLayout},
+    ipc::IpcServer,
+};
+use tmgui::{
+    dates::{self, DateFormat, TimeZoneChoice},
+    event_handler::{API_URL, parse_api_url, parse_proxy},
+    i18n::{Language, tr, tr_with},
+};
+
+use std::path::PathBuf;
+
+/// The file name of settings exported without a path.
+const EXPORT_FILE: &str = "tmgui-settings.ron";
+
+use eframe::egui::{
+    self, Button, ComboBox, DragValue, Grid, Id, Key, Modal, Slider, TextEdit, Window,
+};
+
+impl TempMailApp {
+    pub(super) fn settings_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.settings_open;
+        Window::new(tr("settings_title"))
+            .id(Id::new("settings"))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                Grid::new("settings_grid")
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        self.appearance_settings(ui);
+                        self.font_settings(ui);
+                        self.date_settings(ui);
+                        self.connection_settings(ui);
+                        self.rule_settings(ui);
+                        self.security_settings(ui, ctx);
+                    });
+                ui.separator();
+                self.font_file_settings(ui);
+                ui.separator();
+                self.state_file_settings(ui, ctx);
+                ui.separator();
+                if ui.button(tr("settings_reset")).clicked() {
+                    self.settings = Settings {
+                        overlay: self.settings.overlay.clone(),
+                        ..Default::default()
+                    };
+                    self.window = WindowLayout::default();
+                }
+            });
+        self.settings_open = open;
+    }
+}
+
+impl TempMailApp {
+    fn appearance_settings(&mut self, ui: &mut egui::Ui) {
+        let settings = &mut self.settings;
+        ui.label(tr("settings_language"));
+        ComboBox::from_id_salt("language")
+            .selected_text(
+                settings
+                    .language
+                    .map_or(tr("language_system"), |l| l.name().into()),
+            )
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut settings.language, None, tr("language_system"));
+                for language in Language::ALL {
+                    ui.selectable_value(&mut settings.language, Some(language), language.name());
+                }
+            });
+        ui.end_row();
+
+        ui.label(tr("settings_theme"));
+        ComboBox::from_id_salt("theme")
+            .selected_text(settings.theme.name())
+            .show_ui(ui, |ui| {
+                for theme in ThemeChoice::ALL {
+                    ui.selectable_value(&mut settings.theme, theme, theme.name());
+                }
+            });
+        ui.end_row();
+
+        ui.label(tr("settings_ui_scale"));
+        ui.add(Slider::new(&mut settings.ui_scale, 0.5..=3.).step_by(0.05));
+        ui.end_row();
+
+        ui.label(tr("settings_density"));
+        ComboBox::from_id_salt("density")
+            .selected_text(settings.density.name())
+            .show_ui(ui, |ui| {
+                for density in Density::ALL {
+                    ui.selectable_value(&mut settings.density, density, density.name());
+                }
+            });
+        ui.end_row();
+    }
+
+    fn font_settings(&mut self, ui: &mut egui::Ui) {
+        let settings = &mut self.settings;
+        ui.label(tr("settings_font"));
+        font_selector(ui, "font", &mut settings.font);
+        ui.end_row();
+
+        for (label, size) in [
+            ("settings_heading_size", &mut settings.heading_size),
+            ("settings_body_size", &mut settings.body_size),
+            ("settings_button_size", &mut settings.button_size),
+            ("settings_small_size", &mut settings.small_size),
+            ("settings_monospace_size", &mut settings.monospace_size),
+        ] {
+            ui.label(tr(label));
+            ui.add(Slider::new(size, 8.0..=48.).step_by(1.));
+            ui.end_row();
+        }
+
+        ui.label(tr("settings_email_font"));
+        font_selector(ui, "email_font", &mut settings.email_font);
+        ui.end_row();
+
+        ui.label(tr("settings_email_font_size"));
+        ui.add(Slider::new(&mut settings.email_font_size, 8.0..=48.).step_by(1.));
+        ui.end_row();
+    }
+}
+
+impl TempMailApp {
+    fn date_settings(&mut self, ui: &mut egui::Ui) {
+        let settings = &mut self.settings;
+        ui.label(tr("settings_date_format"));
+        ComboBox::from_id_salt("date_format")
+            .selected_text(settings.date_format.name())
+            .show_ui(ui, |ui| {
+                for format in DateFormat::ALL {
+                    ui.selectable_value(&mut settings.date_format, format, format.name());
+                }
+            });
+        ui.end_row();
+
+        if settings.date_format == DateFormat::Custom {
+            ui.label(tr("settings_date_pattern"));
+            ui.vertical(|ui| {
+                ui.text_edit_singleline(&mut settings.custom_date_format)
+                    .on_hover_text(tr("settings_date_pattern_hint"));
+                if !dates::valid_pattern(&settings.custom_date_format) {
+                    ui.colored_label(ui.visuals().error_fg_color, tr("settings_invalid_pattern"));
+                }
+            });
+            ui.end_row();
+        }
+
+        ui.label(tr("settings_timezone"));
+        ComboBox::from_id_salt("timezone")
+            .selected_text(settings.timezone.name())
+            .show_ui(ui, |ui| {
+                for timezone in TimeZoneChoice::ALL {
+                    ui.selectable_value(&mut settings.timezone, timezone, timezone.name());
+                }
+            });
+        ui.end_row();
+
+        if settings.timezone == TimeZoneChoice::Named {
+            ui.label(tr("settings_zone_name"));
+            ui.vertical(|ui| {
+                ui.text_edit_singleline(&mut settings.timezone_name)
+                    .on_hover_text(tr("settings_zone_name_hint"));
+                if !dates::valid_timezone(&settings.timezone_name) {
+                    ui.colored_label(ui.visuals().error_fg_color, tr("settings_unknown_zone"));
+                }
+            });
+            ui.end_row();
+        }
+    }
+
+    fn connection_settings(&mut self, ui: &mut egui::Ui) {
+        let settings = &mut self.settings;
+        ui.label(tr("settings_proxy"));
+        match &settings.overlay.proxy {
+            Some(proxy) => overridden(ui, proxy),
+            None => {
+                ui.vertical(|ui| {
+                    ui.text_edit_singleline(&mut settings.proxy)
+                        .on_hover_text(tr("settings_proxy_hint"));
+                    if let Err(e) = parse_proxy(&settings.proxy) {
+                        ui.colored_label(ui.visuals().error_fg_color, e);
+                    }
+                });
+            }
+        }
+        ui.end_row();
+
+        ui.label(tr("settings_api_url"));
+        match &settings.overlay.api_url {
+            Some(url) => overridden(ui, url),
+            None => {
+                ui.vertical(|ui| {
+                    ui.add(TextEdit::singleline(&mut settings.api_url).hint_text(API_URL))
+                        .on_hover_text(tr("settings_api_url_hint"));
+                    if !settings.api_url.is_empty()
+                        && let Err(e) = parse_api_url(&settings.api_url)
+                    {
+                        ui.colored_label(ui.visuals().error_fg_color, e);
+                    }
+                });
+            }
+        }
+        ui.end_row();
+
+        ui.label(tr("settings_poll_interval"));
+        match settings.overlay.poll_interval {
+            Some(interval) => overridden(ui, &format!("{} s", interval.as_secs())),
+            None => {
+                ui.add(
+                    DragValue::new(&mut settings.poll_interval_secs)
+                        .range(0..=3600)
+                        .suffix(" s"),
+                )
+                .on_hover_text(tr("settings_poll_interval_hint"));
+            }
+        }
+        ui.end_row();
+
+        let token_hint = match &self.profile_dir {
+            Some(dir) => tr_with(
+                "settings_local_api_hint",
+                &[("path", &IpcServer::token_path(dir).display().to_string())],
+            ),
+            None => tr("settings_local_api_unavailable"),
+        };
+        ui.label(tr("settings_local_api"));
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut settings.ipc_enabled, "")
+                .on_hover_text(&token_hint);
+            ui.add_enabled(
+                !settings.ipc_enabled,
+                DragValue::new(&mut settings.ipc_port).range(1024..=65535),
+            )
+            .on_hover_text(tr("settings_local_api_port"));
+        });
+        ui.end_row();
+    }
+
+    fn rule_settings(&mut self, ui: &mut egui::Ui) {
+        ui.label(tr("rules_title"));
+        let count = self.settings.rules.len().to_string();
+        if ui
+            .button(tr_with("settings_edit_rules", &[("count", &count)]))
+            .clicked()
+        {
+            self.rules_open = true;
+        }
+        ui.end_row();
+
+        ui.label(tr("hooks_title"));
+        if ui.button(tr("settings_edit_hooks")).clicked() {
+            self.hooks_open = true;
+        }
+        ui.end_row();
+    }
+
+    fn security_settings(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
+        ui.label(tr("settings_passphrase"));
+        ui.horizontal(|ui| {
+            if self.vault_key.is_some() {
+                if ui.button(tr("settings_lock_now")).clicked() {
+                    self.lock(ctx);
+                }
+                if ui.button(tr("settings_remove_passphrase")).clicked() {
+                    self.vault_key = None;
+                }
+            } else {
+                ui.add(TextEdit::singleline(&mut self.passphrase).password(true))
+                    .on_hover_text(tr("settings_passphrase_hint"));
+                if ui
+                    .add_enabled(
+                        !self.passphrase.is_empty(),
+                        Button::new(tr("settings_set_passphrase")),
+                    )
+                    .clicked()
+                {
+                    self.set_passphrase();
+                }
+            }
+        });
+        ui.end_row();
+
+        ui.label(tr("settings_auto_lock"));
+        ui.add_enabled(
+            self.vault_key.is_some(),
+            DragValue::new(&mut self.settings.auto_lock_minutes)
+                .range(0..=1440)
+                .suffix(" min"),
+        )
+        .on_hover_text(tr("settings_auto_lock_hint"));
+        ui.end_row();
+
+        ui.label(tr("settings_max_download"));
+        ui.add(
+            DragValue::new(&mut self.settings.max_download_mb)
+                .range(0..=10_000)
+                .suffix(" MB"),
+        )
+        .on_hover_text(tr("settings_max_download_hint"));
+        ui.end_row();
+
+        ui.label(tr("settings_links"));
+        ui.checkbox(&mut self.settings.strip_tracking, tr("link_strip_tracking"));
+        ui.end_row();
+    }
+
+    fn font_file_settings(&mut self, ui: &mut egui::Ui) {
+        ui.label(tr("settings_font_files"));
+        let mut removed = None;
+        for (index, path) in self.settings.font_files.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.small_button("🗑").clicked() {
+                    removed = Some(index);
+                }
+                ui.small(path.display().to_string());
+            });
+        }
+        if let Some(index) = removed {
+            self.settings.font_files.remove(index);
+        }
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.new_font_path);
+            if ui.button(tr("settings_add")).clicked() && !self.new_font_path.trim().is_empty() {
+                let path = std::mem::take(&mut self.new_font_path);
+                self.settings.font_files.push(path.trim().into());
+            }
+        });
+    }
+
+    fn state_file_settings(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
+        ui.label(tr("settings_state_file"));
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.state_path);
+            let path = PathBuf::from(self.state_path.trim());
+            let valid = !self.state_path.trim().is_empty();
+            if ui
+                .add_enabled(valid, Button::new(tr("settings_export")))
+                .clicked()
+            {
+                self.export_settings();
+            }
+            if ui
+                .add_enabled(valid, Button::new(tr("settings_import")))
+                .clicked()
+            {
+                self.import_state(ctx, path, false);
+            }
+        });
+        if let Some(path) = &self.exported {
+            ui.small(tr_with(
+                "settings_exported",
+                &[("path", &path.display().to_string())],
+            ));
+        }
+    }
+
+    /// Exports to the path in the settings window, or to the download
+    /// folder if there is none yet.
+    pub(crate) fn export_settings(&mut self) {
+        if self.state_path.trim().is_empty() {
+            let path = self.settings.download_dir().join(EXPORT_FILE);
+            self.state_path = path.display().to_string();
+        }
+        let path = PathBuf::from(self.state_path.trim());
+        match self.saved_state().export(&path) {
+            Ok(()) => self.exported = Some(path),
+            Err(message) => self.error = Some(message),
+        }
+    }
+
+    /// Takes the shared settings from an exported file and asks before
+    /// taking its sensitive settings.
+    fn import_state(&mut self, ctx: &egui::Context, path: PathBuf, sensitive: bool) {
+        let mut state = self.saved_state();
+        match state.import(&path, sensitive) {
+            Ok(changes) => {
+                self.settings = Settings {
+                    overlay: self.settings.overlay.clone(),
+                    ..state.settings
+                };
+                self.window = state.window;
+                self.window.apply(ctx);
+                if !sensitive && !changes.is_empty() {
+                    self.pending_import = Some((path, changes));
+                }
+            }
+            Err(message) => self.error = Some(message),
+        }
+    }
+
+    pub(super) fn import_dialog(&mut self, ctx: &egui::Context) {
+        let Some((path, changes)) = &self.pending_import else {
+            return;
+        };
+        let mut import = false;
+        let mut close = false;
+        let modal = Modal::new(Id::new("import_dialog")).show(ctx, |ui| {
+            ui.set_max_width(480.);
+            ui.heading(tr("import_sensitive_title"));
+            ui.label(tr_with(
+                "import_sensitive_body",
+                &[("settings", &changes.join(", "))],
+            ));
+            ui.colored_label(ui.visuals().warn_fg_color, tr("import_sensitive_warning"));
+            ui.separator();
+            ui.horizontal(|ui| {
+                import = ui.button(tr("import_sensitive_confirm")).clicked();
+                close = ui.button(tr("import_sensitive_skip")).clicked();
+            });
+        });
+        if import {
+            let path = path.clone();
+            self.import_state(ctx, path, true);
+        }
+        if import || close || modal.should_close() || ctx.input(|i| i.key_pressed(Key::Escape)) {
+            self.pending_import = None;
+        }
+    }
+}
+
+/// A value set by the config file or command line, which the settings can't
+/// change.
+fn overridden(ui: &mut egui::Ui, value: &str) {
+    ui.add_enabled(false, TextEdit::singleline(&mut &*value))
+        .on_disabled_hover_text(tr("settings_overridden"));
+}
+
+fn font_selector(ui: &mut egui::Ui, id: &str, font: &mut FontChoice) {
+    ComboBox::from_id_salt(id)
+        .selected_text(font.name())
+        .show_ui(ui, |ui| {
+            for choice in FontChoice::ALL {
+                ui.selectable_value(font, choice, choice.name());
+            }
+        });
+}
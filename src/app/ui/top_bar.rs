@@ -1,6 +1,8 @@
-use crate::app::TempMailApp;
+use crate::app::{TempMailApp, actions::Action};
+use tmgui::i18n::{tr, tr_with};
 
-use eframe::egui::{self, ComboBox};
+use eframe::egui::{self, Button, ComboBox, DragValue, Grid, Spinner, TextEdit};
+use tmgui::forward::Security;
 
 impl TempMailApp {
     pub(super) fn top_bar(&mut self, ui: &mut egui::Ui, ctx: &eframe::egui::Context) {
@@ -12,25 +14,125 @@ impl TempMailApp {
     fn email_heading(&mut self, ui: &mut egui::Ui, ctx: &eframe::egui::Context) {
         ui.horizontal(|ui| {
             ui.heading([" ", &self.email()].join(""));
-            if ui.button("copy").clicked() {
-                ctx.copy_text(self.email());
+            if ui.button(tr("top_copy")).clicked() {
+                self.run_action(ctx, Action::CopyAddress);
+            }
+            ui.menu_button("⏳", |ui| self.retention_menu(ui))
+                .response
+                .on_hover_text(tr("retention_title"));
+            ui.menu_button("✉", |ui| self.forward_menu(ui))
+                .response
+                .on_hover_text(tr("forward_title"));
+        });
+    }
+    fn retention_menu(&mut self, ui: &mut egui::Ui) {
+        let mut rule = self.retention_rule();
+        ui.label(tr_with("retention_for", &[("address", &self.email())]));
+        ui.horizontal(|ui| {
+            let mut expire = rule.max_age_hours > 0;
+            if ui.checkbox(&mut expire, tr("retention_max_age")).changed() {
+                rule.max_age_hours = if expire { 24 } else { 0 };
+            }
+            if expire {
+                ui.add(
+                    DragValue::new(&mut rule.max_age_hours)
+                        .range(1..=24 * 365)
+                        .suffix(" h"),
+                );
+            }
+        });
+        ui.checkbox(&mut rule.burn_after_reading, tr("retention_burn"))
+            .on_hover_text(tr("retention_burn_hint"));
+        ui.checkbox(&mut rule.clear_on_leave, tr("retention_clear_on_leave"));
+        self.set_retention_rule(rule);
+    }
+    fn forward_menu(&mut self, ui: &mut egui::Ui) {
+        let mut forwarding = self.forwarding_rule();
+        ui.label(tr_with("forward_for", &[("address", &self.email())]));
+        Grid::new("forward_grid").num_columns(2).show(ui, |ui| {
+            ui.label(tr("forward_server"));
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut forwarding.host);
+                ui.add(DragValue::new(&mut forwarding.port).range(1..=65535));
+            });
+            ui.end_row();
+
+            ui.label(tr("forward_security"));
+            let security = forwarding.security;
+            ComboBox::from_id_salt("forward_security")
+                .selected_text(security.name())
+                .show_ui(ui, |ui| {
+                    for choice in Security::ALL {
+                        ui.selectable_value(&mut forwarding.security, choice, choice.name());
+                    }
+                });
+            if forwarding.security != security && forwarding.port == security.default_port() {
+                forwarding.port = forwarding.security.default_port();
+            }
+            ui.end_row();
+
+            ui.label(tr("forward_username"));
+            ui.text_edit_singleline(&mut forwarding.username);
+            ui.end_row();
+
+            ui.label(tr("forward_password"));
+            ui.add(TextEdit::singleline(&mut forwarding.password).password(true));
+            ui.end_row();
+            if self.vault_key.is_none() && !forwarding.password.is_empty() {
+                ui.label("");
+                ui.colored_label(ui.visuals().warn_fg_color, tr("forward_password_unsaved"));
+                ui.end_row();
+            }
+
+            ui.label(tr("forward_from"));
+            ui.text_edit_singleline(&mut forwarding.from)
+                .on_hover_text(tr("forward_from_hint"));
+            ui.end_row();
+
+            ui.label(tr("forward_to"));
+            ui.text_edit_singleline(&mut forwarding.to);
+            ui.end_row();
+        });
+        ui.checkbox(&mut forwarding.automatic, tr("forward_automatic"));
+        let valid = forwarding.validate();
+        self.set_forwarding_rule(forwarding);
+        if let Err(e) = &valid {
+            ui.colored_label(ui.visuals().error_fg_color, e);
+        }
+        ui.horizontal(|ui| {
+            let emails = self.inbox.emails.clone();
+            if ui
+                .add_enabled(
+                    valid.is_ok() && !emails.is_empty(),
+                    Button::new(tr("forward_all")),
+                )
+                .clicked()
+            {
+                self.forward(emails);
+            }
+            if self.forwards_running > 0 {
+                ui.add(Spinner::new());
+            } else if let Some(status) = &self.forward_status {
+                ui.label(status);
             }
         });
     }
     fn email_selector(&mut self, ui: &mut egui::Ui) {
         ui.horizontal(|ui| {
-            let label = ui.label("Name");
-            ui.text_edit_singleline(&mut self.name)
-                .labelled_by(label.id);
+            let label = ui.label(tr("top_name"));
+            self.editing_address = ui
+                .text_edit_singleline(&mut self.inbox.name)
+                .labelled_by(label.id)
+                .has_focus();
             if ui.button("↻").clicked() {
-                self.name = Self::gen_random_name(10);
+                self.run_action(ui.ctx(), Action::NewRandomAddress);
             }
             ui.label("@");
             ComboBox::from_label("")
-                .selected_text(&self.domain)
+                .selected_text(&self.inbox.domain)
                 .show_ui(ui, |ui| {
-                    for i in &self.domains {
-                        ui.selectable_value(&mut self.domain, i.clone(), i);
+                    for i in &self.inbox.domains {
+                        ui.selectable_value(&mut self.inbox.domain, i.clone(), i);
                     }
                 });
         });
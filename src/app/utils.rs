@@ -1,34 +1,49 @@
-use chrono::{Datelike, Month, TimeZone, Timelike};
-use rand::Rng;
+use std::{
+    io,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use eframe::egui;
+use log::error;
+use tmgui::{
+    event_handler::{Event, EventResponse, parse_api_url, parse_proxy},
+    i18n::tr_with,
+    state::{InboxState, random_name},
+};
 
 use crate::{
-    app::TempMailApp,
-    event_handler::{Event, EventResponse},
+    app::{TempMailApp, actions::Action, ui::command_palette::PALETTE_SHORTCUT},
+    config::{
+        file::{CONFIG_CHECK_INTERVAL, ConfigFile},
+        state,
+    },
+    ipc::IpcServer,
 };
+
+const IPC_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
 impl TempMailApp {
-    pub(super) fn gen_random_name(len: usize) -> String {
-        rand::rng()
-            .sample_iter(rand::distr::Alphanumeric)
-            .take(len)
-            .map(|x| x as char)
-            .collect::<String>()
-    }
-    pub(super) fn get_date(received_at: i64) -> String {
-        let date = chrono::Local.timestamp_opt(received_at, 0).unwrap();
-        let (pm, hour) = date.hour12();
-        let minute = date.minute();
-        let weekday = date.weekday().to_string();
-        let month = Month::try_from(date.month() as u8).unwrap().name();
-        let day = date.day();
-        let year = date.year();
-
-        format!(
-            "{weekday}, {month} {day}, {year} at {hour}:{minute} {}",
-            if pm { "PM" } else { "AM" }
-        )
+    pub(super) fn fuzzy_score(query: &str, candidate: &str) -> Option<i32> {
+        let candidate = candidate.to_lowercase().chars().collect::<Vec<_>>();
+        let mut position = 0;
+        let mut previous: Option<usize> = None;
+        let mut score = 0;
+        for c in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+            let index = position + candidate[position..].iter().position(|&x| x == c)?;
+            score += match index {
+                0 => 3,
+                i if previous == Some(i - 1) => 2,
+                i if !candidate[i - 1].is_alphanumeric() => 2,
+                _ => 0,
+            } + 1;
+            previous = Some(index);
+            position = index + 1;
+        }
+        Some(score)
     }
     pub(super) fn email(&self) -> String {
-        [self.name.clone(), self.domain.clone()].join("@")
+        self.inbox.address()
     }
 
     pub(super) fn send_event(&mut self, event: Event) {
@@ -36,20 +51,230 @@ impl TempMailApp {
         let _ = self.events.send(event);
     }
 
+    /// Switches the backend to the configured proxy, leaving invalid URLs
+    /// to the settings window to point out.
+    pub(super) fn set_proxy(&mut self) {
+        if parse_proxy(self.settings.proxy()).is_ok() {
+            let _ = self
+                .events
+                .send(Event::SetProxy(self.settings.proxy().into()));
+            if let Some(ipc) = &self.ipc {
+                ipc.set_proxy(self.settings.proxy());
+            }
+        }
+    }
+
+    /// Switches the backend to the configured API, leaving invalid URLs to
+    /// the settings window to point out.
+    pub(super) fn set_api_url(&mut self) {
+        if parse_api_url(self.settings.api_url()).is_ok() {
+            let _ = self
+                .events
+                .send(Event::SetApiUrl(self.settings.api_url().into()));
+            if let Some(ipc) = &self.ipc {
+                ipc.set_api_url(self.settings.api_url());
+            }
+        }
+    }
+
+    /// Dropping the private directory deletes everything downloaded into it.
+    pub(super) fn set_private(&mut self, private: bool) {
+        self.private = None;
+        if !private {
+            return;
+        }
+        match tempfile::Builder::new().prefix("tmgui-private-").tempdir() {
+            Ok(dir) => self.private = Some(dir),
+            Err(e) => {
+                let error = e.to_string();
+                self.error = Some(tr_with("private_dir_failed", &[("error", &error)]));
+            }
+        }
+    }
+
+    /// Where attachments are downloaded to be opened, only accessible to the
+    /// user.
+    pub(super) fn cache_dir(&self) -> io::Result<PathBuf> {
+        match &self.private {
+            Some(dir) => Ok(dir.path().into()),
+            None => state::create_cache_dir(),
+        }
+    }
+
+    pub(super) fn download_dir(&self) -> PathBuf {
+        match &self.private {
+            Some(dir) => dir.path().into(),
+            None => self.settings.download_dir(),
+        }
+    }
+
+    /// Deletes all local data and continues in private mode with a new
+    /// address.
+    pub(super) fn panic_wipe(&mut self, ctx: &egui::Context) {
+        self.ipc = None;
+        self.vault_key = None;
+        self.locked = None;
+        self.set_private(true);
+        if let Err(e) = state::wipe(self.profile_dir.as_deref()) {
+            let error = e.to_string();
+            self.error = Some(tr_with("wipe_failed", &[("error", &error)]));
+        }
+        let domains = std::mem::take(&mut self.inbox.domains);
+        self.inbox = InboxState::new(random_name(10), self.inbox.domain.clone());
+        self.inbox.domains = domains;
+        self.recent_addresses.clear();
+        self.retention.clear();
+        self.forwarding.clear();
+        self.forget_emails();
+        self.images.clear();
+        self.previews.clear();
+        self.downloads.clear();
+        self.downloaded.clear();
+        self.oversize_allowed.clear();
+        self.archives.clear();
+        self.cards.clear();
+        self.card_saved = None;
+        self.palette = None;
+        ctx.forget_all_images();
+        ctx.memory_mut(|memory| memory.data.clear());
+        ctx.copy_text(String::new());
+        self.send_event(Event::FetchEmails(self.email()));
+        self.send_event(Event::CountEmails(self.email()));
+    }
+
+    pub(super) fn poll(&mut self, ctx: &egui::Context) {
+        if self.polling {
+            ctx.request_repaint_after(Duration::from_millis(200));
+        }
+        let waiting = self.ipc.as_ref().is_some_and(IpcServer::waiting);
+        let interval = match self.settings.poll_interval_secs() {
+            0 if waiting => IPC_POLL_INTERVAL,
+            0 => return,
+            secs => Duration::from_secs(secs),
+        };
+        // Not every half typed address is fetched.
+        if self.inbox.name.is_empty() || self.editing_address || self.locked.is_some() {
+            return;
+        }
+        let elapsed = self.last_poll.elapsed();
+        if elapsed < interval {
+            ctx.request_repaint_after(interval - elapsed);
+            return;
+        }
+        self.last_poll = Instant::now();
+        self.polling = true;
+        let _ = self.events.send(Event::FetchEmails(self.email()));
+        let _ = self.events.send(Event::CountEmails(self.email()));
+    }
+
+    pub(super) fn sync_ipc(&mut self, ctx: &egui::Context) {
+        let wanted = (self.settings.ipc_enabled && self.private.is_none() && self.locked.is_none())
+            .then_some(self.settings.ipc_port);
+        if self.ipc.as_ref().map(IpcServer::port) != wanted {
+            self.ipc = None;
+            if let Some(port) = wanted {
+                let started = match &self.profile_dir {
+                    Some(dir) => IpcServer::start(
+                        ctx,
+                        port,
+                        dir,
+                        self.settings.proxy(),
+                        self.settings.api_url(),
+                    ),
+                    None => Err("The local API needs a storage directory for its token".into()),
+                };
+                match started {
+                    Ok(server) => {
+                        server.publish(&self.email(), &self.inbox.emails);
+                        self.ipc = Some(server);
+                    }
+                    Err(message) => {
+                        error!("{message}");
+                        self.settings.ipc_enabled = false;
+                        self.error = Some(message);
+                    }
+                }
+            }
+        }
+    }
+
+    pub(super) fn reload_config(&mut self) {
+        let (config, mut errors) = ConfigFile::load();
+        self.settings.overlay = config.merge(&self.cli_config);
+
+        self.shortcuts = vec![(Action::CommandPalette, PALETTE_SHORTCUT)];
+        for (name, shortcut) in self.settings.overlay.shortcuts.clone() {
+            match Action::from_name(&name) {
+                Some(action) => {
+                    self.shortcuts.retain(|(bound, _)| *bound != action);
+                    self.shortcuts.push((action, shortcut));
+                }
+                None => errors.push(format!("shortcuts.{name}: unknown action")),
+            }
+        }
+        if !errors.is_empty() {
+            let errors = errors.join("; ");
+            self.error = Some(tr_with("config_invalid", &[("errors", &errors)]));
+        }
+    }
+
+    pub(super) fn watch_config(&mut self, ctx: &egui::Context) {
+        if self.config_watcher.changed() {
+            self.reload_config();
+        }
+        ctx.request_repaint_after(CONFIG_CHECK_INTERVAL);
+    }
+
     pub(super) fn handle_responses(&mut self) {
         if let Ok(response) = self.responses.try_recv() {
             self.fetching = false;
-            match response {
-                EventResponse::Domains(domains) => self.domains = domains,
-                EventResponse::Emails(emails) => self.emails = emails,
-                EventResponse::Email(email) => self.viewed_email = Some(email),
-                EventResponse::Count(c) => self.email_count = c,
-                EventResponse::EmailsMore(emails) => self.emails.extend(emails),
-                EventResponse::EmailsDeleted => self.emails.clear(),
-                EventResponse::Attachments(attachments) => self.attachments = Some(attachments),
-                EventResponse::EmailDeleted(index) => {
-                    self.emails.remove(index);
+            if matches!(
+                response,
+                EventResponse::Emails(..) | EventResponse::Error(_)
+            ) {
+                self.polling = false;
+            }
+            // Emails of an address that was left while they were fetched.
+            if let EventResponse::Emails(address, _) = &response
+                && *address != self.email()
+            {
+                return;
+            }
+            // Answers to requests from before locking would show emails and
+            // errors about the sealed addresses. Unlocking loads them again.
+            if self.locked.is_some() && !matches!(response, EventResponse::Domains(_)) {
+                return;
+            }
+            match &response {
+                EventResponse::AttachmentSaved(path) => self.attachment_saved(path),
+                EventResponse::Attachments(attachments) => self.load_cards(attachments),
+                EventResponse::Email(email) => {
+                    self.read.insert(email.id.clone());
                 }
+                _ => {}
+            }
+            let fetched = match &response {
+                EventResponse::Emails(address, _) => Some(address.clone()),
+                _ => None,
+            };
+            let listed = matches!(
+                response,
+                EventResponse::Emails(..)
+                    | EventResponse::EmailsMore(_)
+                    | EventResponse::EmailsDeleted
+                    | EventResponse::EmailDeleted(_)
+            );
+            if let Err(message) = self.inbox.apply(response) {
+                self.error = Some(message);
+            }
+            if let Some(address) = fetched {
+                self.inbox_opened(&address);
+                self.new_emails();
+            }
+            // Only addresses whose inbox was loaded reach the local API, not
+            // every state of the address field while typing.
+            if listed && let Some(ipc) = &self.ipc {
+                ipc.publish(&self.email(), &self.inbox.emails);
             }
         };
     }
@@ -0,0 +1,251 @@
+//! Looking inside zip, tar and gzip attachments without unpacking them.
+//!
+//! Entries are only read or extracted one by one, so an archive with
+//! thousands of files or a huge unpacked size costs nothing until asked for.
+
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, Read, Seek, SeekFrom},
+    path::{Component, Path, PathBuf},
+};
+
+use flate2::read::GzDecoder;
+use zip::ZipArchive;
+
+/// How much of an entry is read for a preview.
+pub const PREVIEW_LEN: u64 = 64 * 1024;
+/// How much one extraction may unpack in total.
+pub const MAX_EXTRACT_LEN: u64 = 1 << 30;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Kind {
+    Zip,
+    Tar,
+    TarGz,
+    /// A single gzip compressed file.
+    Gz,
+}
+
+impl Kind {
+    /// The kind of archive a file name says it is.
+    pub fn of(filename: &str) -> Option<Self> {
+        let name = filename.to_lowercase();
+        if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if name.ends_with(".gz") {
+            Some(Self::Gz)
+        } else {
+            None
+        }
+    }
+}
+
+/// A file or directory in an archive.
+#[derive(Clone)]
+pub struct Entry {
+    pub name: String,
+    /// Unpacked. For gzip files the size the trailer claims, modulo 4 GiB.
+    pub size: u64,
+    /// Only known for zip entries and gzip files.
+    pub compressed: Option<u64>,
+    pub is_dir: bool,
+}
+
+impl Entry {
+    /// The compressed size as a share of the unpacked size.
+    pub fn ratio(&self) -> Option<f64> {
+        let compressed = self.compressed?;
+        (self.size > 0).then(|| compressed as f64 / self.size as f64)
+    }
+}
+
+/// The entries of an archive in the order they are stored.
+pub fn list(path: &Path, kind: Kind) -> io::Result<Vec<Entry>> {
+    match kind {
+        Kind::Zip => {
+            let mut archive = ZipArchive::new(BufReader::new(File::open(path)?))?;
+            (0..archive.len())
+                .map(|index| {
+                    let file = archive.by_index(index)?;
+                    Ok(Entry {
+                        name: file.name().to_owned(),
+                        size: file.size(),
+                        compressed: Some(file.compressed_size()),
+                        is_dir: file.is_dir(),
+                    })
+                })
+                .collect()
+        }
+        Kind::Tar | Kind::TarGz => {
+            let mut archive = tar::Archive::new(tar_reader(path, kind)?);
+            let mut entries = Vec::new();
+            for entry in archive.entries()? {
+                let entry = entry?;
+                entries.push(Entry {
+                    name: entry.path()?.to_string_lossy().into_owned(),
+                    size: entry.header().size()?,
+                    compressed: None,
+                    is_dir: entry.header().entry_type().is_dir(),
+                });
+            }
+            Ok(entries)
+        }
+        Kind::Gz => Ok(vec![Entry {
+            name: gz_name(path),
+            size: gz_size(path)?,
+            compressed: Some(fs::metadata(path)?.len()),
+            is_dir: false,
+        }]),
+    }
+}
+
+/// The start of an entry as text, or `None` if it looks binary.
+pub fn preview(path: &Path, kind: Kind, name: &str) -> io::Result<Option<String>> {
+    let mut bytes = Vec::new();
+    with_entry(path, kind, name, |reader| {
+        reader.take(PREVIEW_LEN).read_to_end(&mut bytes).map(drop)
+    })?;
+    if bytes.contains(&0) {
+        return Ok(None);
+    }
+    Ok(Some(String::from_utf8_lossy(&bytes).into_owned()))
+}
+
+/// Unpacks the named entries below `dir`, keeping their folders, and returns
+/// where they went. Existing files are kept, an entry of the same name gets
+/// a number appended.
+///
+/// Names that would leave `dir`, like `../x` or `/etc/x`, are refused, and
+/// so is unpacking more than [`MAX_EXTRACT_LEN`].
+pub fn extract(path: &Path, kind: Kind, names: &[String], dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut extracted = Vec::new();
+    let mut remaining = MAX_EXTRACT_LEN;
+    for name in names {
+        let Some(relative) = enclosed(name) else {
+            return Err(io::Error::other(format!("Refusing to extract {name}")));
+        };
+        let target = dir.join(relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let (mut file, target) = create_new(&target)?;
+        let copied = with_entry(path, kind, name, |reader| {
+            io::copy(&mut reader.take(remaining + 1), &mut file).map(drop)
+        })
+        .and_then(|()| file.stream_position());
+        match copied {
+            Ok(size) if size <= remaining => remaining -= size,
+            result => {
+                drop(file);
+                let _ = fs::remove_file(&target);
+                return Err(result.err().unwrap_or_else(|| {
+                    io::Error::other(format!(
+                        "Refusing to unpack more than {MAX_EXTRACT_LEN} bytes"
+                    ))
+                }));
+            }
+        }
+        extracted.push(target);
+    }
+    Ok(extracted)
+}
+
+/// Creates `path`, or `name (1).ext` and so on next to it if it exists.
+fn create_new(path: &Path) -> io::Result<(File, PathBuf)> {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+    let mut candidate = path.to_owned();
+    for number in 1.. {
+        match File::create_new(&candidate) {
+            Ok(file) => return Ok((file, candidate)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                candidate.set_file_name(format!("{stem} ({number}){extension}"));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    unreachable!("an unused name is found first")
+}
+
+/// The entry name as a path that stays inside a directory.
+pub fn enclosed(name: &str) -> Option<PathBuf> {
+    let path = Path::new(name);
+    let safe = path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    let relative = path.components().collect::<PathBuf>();
+    (safe && relative.file_name().is_some()).then_some(relative)
+}
+
+fn with_entry(
+    path: &Path,
+    kind: Kind,
+    name: &str,
+    read: impl FnOnce(&mut dyn Read) -> io::Result<()>,
+) -> io::Result<()> {
+    let not_found = || io::Error::new(io::ErrorKind::NotFound, format!("No entry {name}"));
+    match kind {
+        Kind::Zip => {
+            let mut archive = ZipArchive::new(BufReader::new(File::open(path)?))?;
+            let mut file = archive.by_name(name).map_err(|_| not_found())?;
+            read(&mut file)
+        }
+        Kind::Tar | Kind::TarGz => {
+            let mut archive = tar::Archive::new(tar_reader(path, kind)?);
+            for entry in archive.entries()? {
+                let mut entry = entry?;
+                if entry.path()?.to_string_lossy() == name {
+                    return read(&mut entry);
+                }
+            }
+            Err(not_found())
+        }
+        Kind::Gz if name == gz_name(path) => read(&mut gz_reader(path)?),
+        Kind::Gz => Err(not_found()),
+    }
+}
+
+fn tar_reader(path: &Path, kind: Kind) -> io::Result<Box<dyn Read>> {
+    let file = BufReader::new(File::open(path)?);
+    Ok(match kind {
+        Kind::TarGz => Box::new(GzDecoder::new(file)),
+        _ => Box::new(file),
+    })
+}
+
+/// The unpacked size from the gzip trailer, so listing does not unpack.
+fn gz_size(path: &Path) -> io::Result<u64> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::End(-4))?;
+    let mut size = [0; 4];
+    file.read_exact(&mut size)?;
+    Ok(u32::from_le_bytes(size).into())
+}
+
+fn gz_reader(path: &Path) -> io::Result<GzDecoder<BufReader<File>>> {
+    Ok(GzDecoder::new(BufReader::new(File::open(path)?)))
+}
+
+/// The name of the file in a gzip archive, from the header or else the
+/// archive's name without `.gz`.
+fn gz_name(path: &Path) -> String {
+    let stored = gz_reader(path).ok().and_then(|reader| {
+        let header = reader.header()?;
+        let name = String::from_utf8_lossy(header.filename()?).into_owned();
+        Path::new(&name)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+    });
+    stored.unwrap_or_else(|| {
+        path.file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "file".into())
+    })
+}
@@ -0,0 +1,168 @@
+//! Spotting dangerous attachments and fingerprinting downloaded ones.
+//!
+//! The name and MIME type of an [`Attachment`] are chosen by the sender, so
+//! downloaded files are also checked by their content.
+
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+
+use sha2::{Digest, Sha256};
+use tmapi::Attachment;
+
+use crate::i18n::tr;
+
+/// How much of a file is searched for signs of scripts and macros.
+const SNIFF_LEN: usize = 1 << 20;
+
+const EXECUTABLE_EXTENSIONS: [&str; 22] = [
+    "exe", "dll", "scr", "com", "pif", "cpl", "msi", "msp", "msix", "appx", "jar", "apk", "app",
+    "dmg", "pkg", "deb", "rpm", "elf", "so", "dylib", "iso", "img",
+];
+const SCRIPT_EXTENSIONS: [&str; 20] = [
+    "bat", "cmd", "ps1", "psm1", "vbs", "vbe", "js", "jse", "wsf", "wsh", "hta", "sh", "bash",
+    "py", "pl", "rb", "lnk", "reg", "scf", "url",
+];
+const MACRO_EXTENSIONS: [&str; 9] = [
+    "docm", "dotm", "xlsm", "xltm", "xlam", "pptm", "potm", "ppam", "sldm",
+];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Risk {
+    Executable,
+    Script,
+    /// An office document with macros.
+    Macro,
+}
+
+impl Risk {
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Executable => tr("attachment_risk_executable"),
+            Self::Script => tr("attachment_risk_script"),
+            Self::Macro => tr("attachment_risk_macro"),
+        }
+    }
+}
+
+/// The risk the name or MIME type of an attachment claims.
+pub fn declared_risk(attachment: &Attachment) -> Option<Risk> {
+    let extension = attachment
+        .filename
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_lowercase())
+        .unwrap_or_default();
+    let content_type = attachment.content_type.to_lowercase();
+    if EXECUTABLE_EXTENSIONS.contains(&extension.as_str())
+        || content_type.contains("msdownload")
+        || content_type.contains("executable")
+        || content_type == "application/java-archive"
+        || content_type == "application/vnd.android.package-archive"
+    {
+        Some(Risk::Executable)
+    } else if MACRO_EXTENSIONS.contains(&extension.as_str())
+        || content_type.contains("macroenabled")
+    {
+        Some(Risk::Macro)
+    } else if SCRIPT_EXTENSIONS.contains(&extension.as_str())
+        || content_type.contains("javascript")
+        || content_type.contains("x-sh")
+        || content_type.contains("hta")
+    {
+        Some(Risk::Script)
+    } else {
+        None
+    }
+}
+
+/// The risk of a file judging by its first bytes.
+pub fn sniff(bytes: &[u8]) -> Option<Risk> {
+    const MACHO: [[u8; 4]; 4] = [
+        [0xfe, 0xed, 0xfa, 0xce],
+        [0xfe, 0xed, 0xfa, 0xcf],
+        [0xcf, 0xfa, 0xed, 0xfe],
+        [0xca, 0xfe, 0xba, 0xbe],
+    ];
+    let contains = |needle: &[u8]| bytes.windows(needle.len()).any(|w| w == needle);
+    if bytes.starts_with(b"MZ") || bytes.starts_with(b"\x7fELF") {
+        return Some(Risk::Executable);
+    }
+    if MACHO.iter().any(|magic| bytes.starts_with(magic)) {
+        return Some(Risk::Executable);
+    }
+    if bytes.starts_with(b"#!") {
+        return Some(Risk::Script);
+    }
+    // Zip archives, which office documents are, name their entries in plain
+    // text, and so do the older OLE documents.
+    if bytes.starts_with(b"PK\x03\x04") {
+        if contains(b"vbaProject.bin") {
+            return Some(Risk::Macro);
+        }
+        if contains(b"META-INF/MANIFEST.MF") || contains(b"classes.dex") {
+            return Some(Risk::Executable);
+        }
+        return None;
+    }
+    if bytes.starts_with(b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1") {
+        let macros = contains(b"_VBA_PROJECT") || contains(&utf16(b"_VBA_PROJECT"));
+        return macros.then_some(Risk::Macro);
+    }
+    if bytes.starts_with(b"%PDF") {
+        let active = contains(b"/JavaScript") || contains(b"/Launch");
+        return active.then_some(Risk::Script);
+    }
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(4096)]).to_lowercase();
+    (head.contains("<script") || head.contains("<hta:application")).then_some(Risk::Script)
+}
+
+fn utf16(ascii: &[u8]) -> Vec<u8> {
+    ascii.iter().flat_map(|&byte| [byte, 0]).collect()
+}
+
+/// What a downloaded file turned out to be.
+#[derive(Clone)]
+pub struct FileCheck {
+    /// Lowercase hex.
+    pub sha256: String,
+    pub size: u64,
+    pub risk: Option<Risk>,
+}
+
+/// Hashes a file and sniffs its content.
+pub fn check_file(path: &Path) -> io::Result<FileCheck> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut head = Vec::new();
+    let mut size = 0;
+    let mut buffer = [0; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        if head.len() < SNIFF_LEN {
+            let take = read.min(SNIFF_LEN - head.len());
+            head.extend_from_slice(&buffer[..take]);
+        }
+        size += read as u64;
+    }
+    Ok(FileCheck {
+        sha256: hex(&hasher.finalize()),
+        size,
+        risk: sniff(&head),
+    })
+}
+
+/// A directory name for an attachment id. The server chooses ids, so they
+/// are hashed rather than trusted as a path.
+pub fn id_dir(id: &str) -> String {
+    hex(&Sha256::digest(id.as_bytes())[..16])
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
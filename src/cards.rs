@@ -0,0 +1,601 @@
+//! Calendar invites (iCalendar, RFC 5545) and contact cards (vCard, RFC 6350)
+//! in attachments.
+//!
+//! Both are lines of `NAME;PARAM=value:value` between `BEGIN` and `END`, so
+//! one reader covers them. Only what is shown is parsed.
+
+use chrono::{Datelike, Days, FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use tmapi::Attachment;
+
+use crate::{
+    dates::DateFormatter,
+    i18n::{tr, tr_with},
+};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum CardKind {
+    Calendar,
+    Contact,
+}
+
+impl CardKind {
+    /// The kind of card an attachment holds judging by its name and MIME type.
+    pub fn of(attachment: &Attachment) -> Option<Self> {
+        let name = attachment.filename.to_lowercase();
+        let content_type = attachment.content_type.to_lowercase();
+        if name.ends_with(".ics") || content_type.starts_with("text/calendar") {
+            Some(Self::Calendar)
+        } else if name.ends_with(".vcf")
+            || name.ends_with(".vcard")
+            || content_type.starts_with("text/vcard")
+            || content_type.starts_with("text/x-vcard")
+        {
+            Some(Self::Contact)
+        } else {
+            None
+        }
+    }
+}
+
+/// The cards in an attachment.
+pub enum Cards {
+    Events(Vec<CalendarEvent>),
+    Contacts(Vec<Contact>),
+}
+
+impl Cards {
+    pub fn parse(kind: CardKind, text: &str) -> Self {
+        match kind {
+            CardKind::Calendar => Self::Events(events(text)),
+            CardKind::Contact => Self::Contacts(contacts(text)),
+        }
+    }
+}
+
+pub enum EventTime {
+    /// A whole day.
+    Date(NaiveDate),
+    /// A moment, as a Unix timestamp.
+    At(i64),
+    /// The same wall clock time in every zone, or in a zone that is not known.
+    Floating(NaiveDateTime),
+}
+
+/// Outlook and Exchange name zones the Windows way. The common ones, from the
+/// Unicode CLDR mapping.
+const WINDOWS_ZONES: &[(&str, &str)] = &[
+    ("Dateline Standard Time", "Etc/GMT+12"),
+    ("Hawaiian Standard Time", "Pacific/Honolulu"),
+    ("Alaskan Standard Time", "America/Anchorage"),
+    ("Pacific Standard Time", "America/Los_Angeles"),
+    ("US Mountain Standard Time", "America/Phoenix"),
+    ("Mountain Standard Time", "America/Denver"),
+    ("Central Standard Time", "America/Chicago"),
+    ("Central Standard Time (Mexico)", "America/Mexico_City"),
+    ("Canada Central Standard Time", "America/Regina"),
+    ("SA Pacific Standard Time", "America/Bogota"),
+    ("Eastern Standard Time", "America/New_York"),
+    ("Atlantic Standard Time", "America/Halifax"),
+    ("Newfoundland Standard Time", "America/St_Johns"),
+    ("Pacific SA Standard Time", "America/Santiago"),
+    ("E. South America Standard Time", "America/Sao_Paulo"),
+    ("Argentina Standard Time", "America/Buenos_Aires"),
+    ("UTC", "Etc/UTC"),
+    ("GMT Standard Time", "Europe/London"),
+    ("Greenwich Standard Time", "Atlantic/Reykjavik"),
+    ("W. Europe Standard Time", "Europe/Berlin"),
+    ("Central Europe Standard Time", "Europe/Budapest"),
+    ("Romance Standard Time", "Europe/Paris"),
+    ("Central European Standard Time", "Europe/Warsaw"),
+    ("W. Central Africa Standard Time", "Africa/Lagos"),
+    ("GTB Standard Time", "Europe/Bucharest"),
+    ("E. Europe Standard Time", "Europe/Chisinau"),
+    ("FLE Standard Time", "Europe/Kiev"),
+    ("Egypt Standard Time", "Africa/Cairo"),
+    ("South Africa Standard Time", "Africa/Johannesburg"),
+    ("Israel Standard Time", "Asia/Jerusalem"),
+    ("Turkey Standard Time", "Europe/Istanbul"),
+    ("Russian Standard Time", "Europe/Moscow"),
+    ("Arab Standard Time", "Asia/Riyadh"),
+    ("Arabian Standard Time", "Asia/Dubai"),
+    ("Iran Standard Time", "Asia/Tehran"),
+    ("Pakistan Standard Time", "Asia/Karachi"),
+    ("India Standard Time", "Asia/Calcutta"),
+    ("Bangladesh Standard Time", "Asia/Dhaka"),
+    ("SE Asia Standard Time", "Asia/Bangkok"),
+    ("China Standard Time", "Asia/Shanghai"),
+    ("Singapore Standard Time", "Asia/Singapore"),
+    ("Taipei Standard Time", "Asia/Taipei"),
+    ("W. Australia Standard Time", "Australia/Perth"),
+    ("Tokyo Standard Time", "Asia/Tokyo"),
+    ("Korea Standard Time", "Asia/Seoul"),
+    ("Cen. Australia Standard Time", "Australia/Adelaide"),
+    ("E. Australia Standard Time", "Australia/Brisbane"),
+    ("AUS Eastern Standard Time", "Australia/Sydney"),
+    ("New Zealand Standard Time", "Pacific/Auckland"),
+];
+
+impl EventTime {
+    pub fn format(&self, dates: &DateFormatter) -> String {
+        match self {
+            Self::Date(date) => dates.day(*date),
+            Self::At(timestamp) => dates.event(*timestamp),
+            Self::Floating(time) => tr_with("card_floating", &[("time", &dates.wall_clock(*time))]),
+        }
+    }
+}
+
+pub struct CalendarEvent {
+    pub summary: String,
+    pub start: Option<EventTime>,
+    pub end: Option<EventTime>,
+    pub location: Option<String>,
+    pub organizer: Option<String>,
+    /// The raw `RRULE`.
+    pub recurrence: Option<String>,
+    pub description: Option<String>,
+    /// The invite was withdrawn.
+    pub cancelled: bool,
+    /// The whole calendar, with the time zones the event refers to.
+    pub source: String,
+}
+
+impl CalendarEvent {
+    /// The event as lines of text, to copy.
+    pub fn details(&self, dates: &DateFormatter) -> String {
+        let time = |time: &Option<EventTime>| time.as_ref().map(|time| time.format(dates));
+        [
+            Some(self.summary.clone()),
+            time(&self.start),
+            time(&self.end),
+            self.location.clone(),
+            self.organizer.clone(),
+            self.recurrence.as_deref().map(describe_recurrence),
+            self.description.clone(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join("\n")
+    }
+}
+
+pub struct Contact {
+    pub name: String,
+    pub organization: Option<String>,
+    pub emails: Vec<String>,
+    pub phones: Vec<String>,
+    /// The card as it was sent.
+    pub source: String,
+}
+
+impl Contact {
+    /// The contact as lines of text, to copy.
+    pub fn details(&self) -> String {
+        let mut lines = vec![self.name.clone()];
+        lines.extend(self.organization.clone());
+        lines.extend(self.emails.iter().cloned());
+        lines.extend(self.phones.iter().cloned());
+        lines.join("\n")
+    }
+}
+
+struct Property {
+    /// The unfolded line.
+    line: String,
+    /// Uppercase, like the parameter keys.
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The value with `\,`, `\;`, `\n` and `\\` unescaped.
+    fn text(&self) -> String {
+        let mut text = String::with_capacity(self.value.len());
+        let mut chars = self.value.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                text.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('n' | 'N') => text.push('\n'),
+                Some(escaped) => text.push(escaped),
+                None => {}
+            }
+        }
+        text
+    }
+}
+
+/// The calendar events in an iCalendar file.
+pub fn events(text: &str) -> Vec<CalendarEvent> {
+    let properties = properties(text);
+    let zones = components(&properties, "VTIMEZONE")
+        .filter_map(Zone::parse)
+        .collect::<Vec<_>>();
+    let event_time = |property: &Property| event_time(property, &zones);
+    let cancelled_calendar = properties
+        .iter()
+        .any(|p| p.name == "METHOD" && p.value.eq_ignore_ascii_case("CANCEL"));
+    components(&properties, "VEVENT")
+        .map(|event| {
+            let event = own(event);
+            let find = |name: &str| event.iter().copied().find(|p| p.name == name);
+            CalendarEvent {
+                summary: find("SUMMARY").map(Property::text).unwrap_or_default(),
+                start: find("DTSTART").and_then(&event_time),
+                end: find("DTEND").and_then(&event_time),
+                location: find("LOCATION").map(Property::text),
+                organizer: find("ORGANIZER").map(person),
+                recurrence: find("RRULE").map(|rule| rule.value.clone()),
+                description: find("DESCRIPTION").map(Property::text),
+                cancelled: cancelled_calendar
+                    || find("STATUS").is_some_and(|s| s.value.eq_ignore_ascii_case("CANCELLED")),
+                source: text.to_owned(),
+            }
+        })
+        .collect()
+}
+
+/// The contacts in a vCard file.
+pub fn contacts(text: &str) -> Vec<Contact> {
+    let properties = properties(text);
+    components(&properties, "VCARD")
+        .map(|card| {
+            let all = |name: &str| {
+                card.iter()
+                    .filter(|p| p.name == name)
+                    .map(Property::text)
+                    .filter(|value| !value.is_empty())
+                    .collect::<Vec<_>>()
+            };
+            let name = all("FN").into_iter().next().unwrap_or_else(|| {
+                // N is family;given;additional;prefix;suffix.
+                let parts = all("N").into_iter().next().unwrap_or_default();
+                let parts = parts.split(';').collect::<Vec<_>>();
+                [parts.get(1), parts.first()]
+                    .into_iter()
+                    .flatten()
+                    .filter(|part| !part.is_empty())
+                    .copied()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            });
+            let phones = all("TEL")
+                .into_iter()
+                .map(|phone| phone.trim_start_matches("tel:").to_owned())
+                .collect();
+            Contact {
+                name,
+                organization: all("ORG")
+                    .into_iter()
+                    .next()
+                    .map(|org| org.trim_matches(';').replace(';', ", ")),
+                emails: all("EMAIL"),
+                phones,
+                source: vcard_source(card),
+            }
+        })
+        .collect()
+}
+
+/// A recurrence rule like `FREQ=WEEKLY;INTERVAL=2;COUNT=5` in words.
+pub fn describe_recurrence(rule: &str) -> String {
+    let part = |name: &str| {
+        rule.split(';')
+            .filter_map(|part| part.split_once('='))
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+    };
+    let interval = part("INTERVAL").and_then(|i| i.parse::<u32>().ok());
+    let interval = interval.unwrap_or(1).to_string();
+    let key = match (
+        part("FREQ").map(str::to_uppercase).as_deref(),
+        interval == "1",
+    ) {
+        (Some("DAILY"), true) => "recur_daily",
+        (Some("DAILY"), false) => "recur_every_days",
+        (Some("WEEKLY"), true) => "recur_weekly",
+        (Some("WEEKLY"), false) => "recur_every_weeks",
+        (Some("MONTHLY"), true) => "recur_monthly",
+        (Some("MONTHLY"), false) => "recur_every_months",
+        (Some("YEARLY"), true) => "recur_yearly",
+        (Some("YEARLY"), false) => "recur_every_years",
+        _ => return rule.to_owned(),
+    };
+    let mut parts = vec![tr_with(key, &[("count", &interval)])];
+    if let Some(days) = part("BYDAY") {
+        parts.push(tr_with("recur_on", &[("days", days)]));
+    }
+    if let Some(count) = part("COUNT") {
+        parts.push(tr_with("recur_times", &[("count", count)]));
+    }
+    if let Some(until) = part("UNTIL") {
+        let date = until
+            .get(..8)
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y%m%d").ok());
+        let until = date.map_or(until.to_owned(), |date| date.to_string());
+        parts.push(tr_with("recur_until", &[("date", &until)]));
+    }
+    parts.join(", ")
+}
+
+/// The unfolded content lines. A line starting with a space or tab continues
+/// the previous one.
+fn properties(text: &str) -> Vec<Property> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_owned()),
+        }
+    }
+    lines.iter().filter_map(|line| property(line)).collect()
+}
+
+fn property(line: &str) -> Option<Property> {
+    // The value starts at the first colon outside a quoted parameter.
+    let mut quoted = false;
+    let colon = line.char_indices().find_map(|(index, c)| match c {
+        '"' => {
+            quoted = !quoted;
+            None
+        }
+        ':' if !quoted => Some(index),
+        _ => None,
+    })?;
+    let (head, value) = (&line[..colon], &line[colon + 1..]);
+    let mut head = head.split(';');
+    // vCard lets properties be grouped, as in `item1.EMAIL`.
+    let name = head.next()?.rsplit('.').next()?.to_uppercase();
+    let params = head
+        .filter_map(|param| param.split_once('='))
+        .map(|(key, value)| (key.to_uppercase(), value.trim_matches('"').to_owned()))
+        .collect();
+    Some(Property {
+        line: line.to_owned(),
+        name,
+        params,
+        value: value.to_owned(),
+    })
+}
+
+/// The properties of each top-level `BEGIN:name` ... `END:name` block.
+fn components<'a>(
+    properties: &'a [Property],
+    name: &'a str,
+) -> impl Iterator<Item = &'a [Property]> + 'a {
+    let is = move |property: &Property, marker: &str| {
+        property.name == marker && property.value.eq_ignore_ascii_case(name)
+    };
+    properties
+        .iter()
+        .enumerate()
+        .filter(move |(_, property)| is(property, "BEGIN"))
+        .filter_map(move |(start, _)| {
+            let length = properties[start..]
+                .iter()
+                .position(|property| is(property, "END"))?;
+            Some(&properties[start..start + length + 1])
+        })
+}
+
+/// The properties of a component without those of the components in it, like
+/// the alarms of an event.
+fn own(component: &[Property]) -> Vec<&Property> {
+    let mut depth = 0;
+    let mut own = Vec::new();
+    for property in component {
+        match property.name.as_str() {
+            "BEGIN" => depth += 1,
+            "END" => depth -= 1,
+            _ if depth == 1 => own.push(property),
+            _ => {}
+        }
+    }
+    own
+}
+
+/// A `VTIMEZONE` of the calendar, for `TZID`s that are no IANA names.
+struct Zone {
+    id: String,
+    observances: Vec<Observance>,
+}
+
+/// The `STANDARD` or `DAYLIGHT` part of a [`Zone`].
+struct Observance {
+    start: NaiveDateTime,
+    /// East of UTC.
+    offset: FixedOffset,
+    /// The yearly start as month, week of the month (negative from its end)
+    /// and day, from an `RRULE` like `FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU`.
+    yearly: Option<(u32, i32, Weekday)>,
+}
+
+impl Zone {
+    fn parse(component: &[Property]) -> Option<Self> {
+        let id = own(component).into_iter().find(|p| p.name == "TZID")?;
+        let observances = ["STANDARD", "DAYLIGHT"]
+            .into_iter()
+            .flat_map(|name| components(&component[1..], name))
+            .filter_map(Observance::parse)
+            .collect::<Vec<_>>();
+        (!observances.is_empty()).then(|| Self {
+            id: id.value.trim().to_owned(),
+            observances,
+        })
+    }
+
+    /// The offset in effect at a local time: that of the observance that
+    /// started last before it, or else the first one.
+    fn offset(&self, time: NaiveDateTime) -> FixedOffset {
+        let onsets = self.observances.iter().flat_map(|observance| {
+            let onsets = match observance.yearly {
+                Some((month, week, day)) => [time.year() - 1, time.year()]
+                    .into_iter()
+                    .filter_map(|year| nth_weekday(year, month, week, day))
+                    .map(|date| date.and_time(observance.start.time()))
+                    .filter(|onset| *onset >= observance.start)
+                    .collect(),
+                None => vec![observance.start],
+            };
+            onsets
+                .into_iter()
+                .map(move |onset| (onset, observance.offset))
+        });
+        onsets
+            .filter(|(onset, _)| *onset <= time)
+            .max_by_key(|(onset, _)| *onset)
+            .map(|(_, offset)| offset)
+            .unwrap_or_else(|| {
+                let first = self.observances.iter().min_by_key(|o| o.start);
+                first.map_or(self.observances[0].offset, |o| o.offset)
+            })
+    }
+}
+
+impl Observance {
+    fn parse(component: &[Property]) -> Option<Self> {
+        let own = own(component);
+        let find = |name: &str| own.iter().copied().find(|p| p.name == name);
+        let start = find("DTSTART")?.value.trim().to_owned();
+        let start = NaiveDateTime::parse_from_str(&start, "%Y%m%dT%H%M%S").ok()?;
+        let offset = utc_offset(&find("TZOFFSETTO")?.value)?;
+        let yearly = find("RRULE").and_then(|rule| {
+            let part = |name: &str| {
+                rule.value
+                    .split(';')
+                    .filter_map(|part| part.split_once('='))
+                    .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                    .map(|(_, value)| value)
+            };
+            let month = part("BYMONTH")?.parse().ok()?;
+            let by_day = part("BYDAY")?;
+            let (week, day) = by_day.split_at(by_day.len().checked_sub(2)?);
+            Some((month, week.parse().ok()?, weekday(day)?))
+        });
+        Some(Self {
+            start,
+            offset,
+            yearly,
+        })
+    }
+}
+
+/// A `TZOFFSETTO` like `+0100` or `-053000`.
+fn utc_offset(value: &str) -> Option<FixedOffset> {
+    let value = value.trim();
+    let sign = match value.get(..1)? {
+        "+" => 1,
+        "-" => -1,
+        _ => return None,
+    };
+    let digits = &value[1..];
+    let part = |range: std::ops::Range<usize>| digits.get(range)?.parse::<i32>().ok();
+    let seconds = part(0..2)? * 3600 + part(2..4)? * 60 + part(4..6).unwrap_or(0);
+    FixedOffset::east_opt(sign * seconds)
+}
+
+fn weekday(day: &str) -> Option<Weekday> {
+    Some(match day.to_uppercase().as_str() {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    })
+}
+
+/// The `week`th `day` of a month, counting from its end if negative.
+fn nth_weekday(year: i32, month: u32, week: i32, day: Weekday) -> Option<NaiveDate> {
+    if week > 0 {
+        return NaiveDate::from_weekday_of_month_opt(year, month, day, week.try_into().ok()?);
+    }
+    let next_month = match month {
+        12 => NaiveDate::from_ymd_opt(year + 1, 1, 1)?,
+        _ => NaiveDate::from_ymd_opt(year, month + 1, 1)?,
+    };
+    let last = next_month.pred_opt()?;
+    let back = (last.weekday().num_days_from_monday() + 7 - day.num_days_from_monday()) % 7;
+    let weeks_back = u64::try_from(-week - 1).ok()?;
+    let date = last.checked_sub_days(Days::new(u64::from(back) + 7 * weeks_back))?;
+    (date.month() == month).then_some(date)
+}
+
+/// Times in a `TZID` zone are looked up as an IANA name, in the calendar's
+/// own `VTIMEZONE`s and as a Windows name, in that order.
+fn event_time(property: &Property, zones: &[Zone]) -> Option<EventTime> {
+    let value = property.value.trim();
+    if property.param("VALUE") == Some("DATE") || value.len() == 8 {
+        return NaiveDate::parse_from_str(value, "%Y%m%d")
+            .ok()
+            .map(EventTime::Date);
+    }
+    let time = NaiveDateTime::parse_from_str(value.trim_end_matches('Z'), "%Y%m%dT%H%M%S").ok()?;
+    if value.ends_with('Z') {
+        return Some(EventTime::At(Utc.from_utc_datetime(&time).timestamp()));
+    }
+    let Some(id) = property.param("TZID") else {
+        return Some(EventTime::Floating(time));
+    };
+    let iana = |name: &str| {
+        let zone = name.parse::<Tz>().ok()?;
+        zone.from_local_datetime(&time).earliest()
+    };
+    let at = iana(id).map(|time| time.timestamp()).or_else(|| {
+        let zone = zones.iter().find(|zone| zone.id == id);
+        let own = zone.and_then(|zone| zone.offset(time).from_local_datetime(&time).single());
+        own.map(|time| time.timestamp()).or_else(|| {
+            let (_, name) = WINDOWS_ZONES.iter().find(|(windows, _)| *windows == id)?;
+            iana(name).map(|time| time.timestamp())
+        })
+    });
+    Some(at.map_or(EventTime::Floating(time), EventTime::At))
+}
+
+/// A `CN=Name:mailto:address` property as `Name <address>`.
+fn person(property: &Property) -> String {
+    let address = property.value.trim();
+    let address = address
+        .strip_prefix("mailto:")
+        .or_else(|| address.strip_prefix("MAILTO:"))
+        .unwrap_or(address);
+    match property.param("CN") {
+        Some(name) if !name.is_empty() => format!("{name} <{address}>"),
+        _ => address.to_owned(),
+    }
+}
+
+fn vcard_source(card: &[Property]) -> String {
+    let lines = card.iter().map(|property| property.line.as_str());
+    lines.collect::<Vec<_>>().join("\r\n") + "\r\n"
+}
+
+/// A file name for exporting a card, from its title.
+pub fn export_name(title: &str, extension: &str) -> String {
+    let name = title
+        .chars()
+        .map(|c| match c.is_alphanumeric() || c == ' ' || c == '-' {
+            true => c,
+            false => '_',
+        })
+        .collect::<String>();
+    let name = name.trim();
+    match name.is_empty() {
+        true => format!("{}.{extension}", tr("card_untitled")),
+        false => format!("{name}.{extension}"),
+    }
+}
@@ -0,0 +1,264 @@
+use std::{collections::HashMap, io, path::PathBuf, str::FromStr, time::Duration};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use regex::Regex;
+use tmgui::event_handler::parse_api_url;
+
+use crate::config::{
+    file::{ConfigFile, parse_poll_interval},
+    state::{PROFILES_DIR, STATE_BACKUP, STATE_FILE, STATE_KEY, SavedState},
+};
+
+pub const APP_ID: &str = "adenosine.tmgui";
+
+#[derive(Parser)]
+#[command(version, about)]
+pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Full address to open, e.g. user@vwh.sh
+    #[arg(long, global = true, conflicts_with_all = ["name", "domain"])]
+    pub address: Option<Address>,
+    /// Name part of the address
+    #[arg(long, global = true)]
+    pub name: Option<String>,
+    /// Domain part of the address
+    #[arg(long, global = true)]
+    pub domain: Option<String>,
+    /// Start with a new random name
+    #[arg(long, conflicts_with_all = ["address", "name"])]
+    pub random: bool,
+    /// Send API requests through this proxy, e.g. http://proxy:3128 or
+    /// socks5h://localhost:9050
+    #[arg(long, global = true)]
+    pub proxy: Option<String>,
+    /// Use the API at this URL instead of https://api.barid.site, e.g. a
+    /// self-hosted instance
+    #[arg(long, global = true, value_parser = valid_api_url)]
+    pub api_url: Option<String>,
+    /// Keep settings and state in a separate storage directory
+    #[arg(long, global = true, value_parser = parse_profile)]
+    pub profile: Option<String>,
+    /// Poll the inbox at this interval, e.g. 30s or 2m
+    #[arg(long, value_parser = parse_poll_interval)]
+    pub poll_interval: Option<Duration>,
+    /// Print JSON instead of text in subcommands
+    #[arg(long, global = true)]
+    pub json: bool,
+    /// Run in the terminal instead of opening a window
+    #[arg(long)]
+    pub tui: bool,
+    /// Store nothing on disk and delete downloaded attachments on exit
+    #[arg(long, global = true)]
+    pub private: bool,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// List the emails of the address
+    List,
+    /// Print an email
+    Read { id: String },
+    /// Delete an email
+    Delete { id: String },
+    /// List the available domains
+    Domains,
+    /// Print a new random address
+    NewAddress,
+    /// Write the appearance, polling and API settings and the window layout
+    /// to a file to share them
+    ExportSettings { path: PathBuf },
+    /// Take the appearance, polling and API settings and the window layout
+    /// from an exported file
+    ImportSettings {
+        path: PathBuf,
+        /// Also take the proxy, download folder, local API, hooks and rules
+        /// from the file
+        #[arg(long)]
+        sensitive: bool,
+    },
+    /// List the attachments of an email
+    Attachments {
+        id: String,
+        /// Download the attachments into this directory
+        #[arg(long)]
+        save: Option<PathBuf>,
+        /// Also download attachments larger than the download limit
+        #[arg(long, requires = "save")]
+        allow_large: bool,
+    },
+    /// Wait until a matching email arrives and print it
+    WaitFor(WaitFor),
+}
+
+#[derive(clap::Args)]
+pub struct WaitFor {
+    /// Regex the sender has to match
+    #[arg(long)]
+    pub from: Option<Regex>,
+    /// Regex the subject has to match
+    #[arg(long)]
+    pub subject: Option<Regex>,
+    /// Regex the body has to match
+    #[arg(long)]
+    pub body: Option<Regex>,
+    /// Give up after this long, e.g. 120s or 5m
+    #[arg(long, default_value = "120s", value_parser = humantime::parse_duration)]
+    pub timeout: Duration,
+    /// Check the inbox at this interval
+    #[arg(long, default_value = "5s", value_parser = humantime::parse_duration)]
+    pub interval: Duration,
+    /// Print only the verification code or link of the email. A matching
+    /// email without one is skipped for good and named if the wait times out
+    #[arg(long)]
+    pub extract: Option<Extract>,
+    /// Ignore emails that are already in the inbox
+    #[arg(long)]
+    pub new_only: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Extract {
+    Code,
+    Link,
+}
+
+impl Args {
+    pub fn profile_dir(&self) -> Option<PathBuf> {
+        let base = eframe::storage_dir(APP_ID)?;
+        Some(match &self.profile {
+            Some(profile) => base.join(PROFILES_DIR).join(profile),
+            None => base,
+        })
+    }
+
+    /// The values in the state file, none if there is no file yet.
+    fn stored_values(&self) -> Result<HashMap<String, String>, String> {
+        let Some(path) = self.profile_dir().map(|dir| dir.join(STATE_FILE)) else {
+            return Ok(HashMap::new());
+        };
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(HashMap::new()),
+            Err(e) => return Err(format!("Could not read {}: {e}", path.display())),
+        };
+        ron::from_str(&text).map_err(|e| format!("Could not read {}: {e}", path.display()))
+    }
+
+    /// The stored state, or an error if the state file is damaged.
+    pub fn stored_state(&self) -> Result<SavedState, String> {
+        let stored = self.stored_values()?;
+        SavedState::load(|key| stored.get(key).cloned()).map_err(|e| {
+            let path = self.profile_dir().unwrap_or_default().join(STATE_FILE);
+            format!("Could not read {}: {e}", path.display())
+        })
+    }
+
+    /// The stored state with the address and settings given on the command
+    /// line, `--random` drops the stored name.
+    ///
+    /// A damaged state file is moved to [`STATE_BACKUP`] so the defaults
+    /// saved later don't replace it, and the returned message says so.
+    pub fn start_state(&self) -> (SavedState, Option<String>) {
+        let (mut state, error) = match self.stored_state() {
+            Ok(state) => (state, None),
+            Err(message) => (SavedState::default(), Some(self.back_up_state(message))),
+        };
+        let (name, domain) = self.name_and_domain();
+        if self.random {
+            state.name = None;
+        }
+        state.name = name.or(state.name);
+        state.domain = domain.or(state.domain);
+        (state, error)
+    }
+
+    fn back_up_state(&self, message: String) -> String {
+        let Some(dir) = self.profile_dir().filter(|_| !self.private) else {
+            return message;
+        };
+        let backup = dir.join(STATE_BACKUP);
+        match std::fs::rename(dir.join(STATE_FILE), &backup) {
+            Ok(()) => format!(
+                "{message}. It was moved to {} and the defaults are used.",
+                backup.display()
+            ),
+            Err(e) => format!("{message}. Moving it aside failed too: {e}"),
+        }
+    }
+
+    pub fn store_state(&self, state: &SavedState) -> io::Result<()> {
+        if self.private {
+            return Err(io::Error::other("Nothing is stored in private mode"));
+        }
+        let dir = self
+            .profile_dir()
+            .ok_or_else(|| io::Error::other("No storage directory"))?;
+        let path = dir.join(STATE_FILE);
+        // A damaged file is left alone rather than replaced.
+        let mut stored = self.stored_values().map_err(io::Error::other)?;
+        let state = ron::ser::to_string(state).map_err(io::Error::other)?;
+        stored.insert(STATE_KEY.into(), state);
+        let text =
+            ron::ser::to_string_pretty(&stored, Default::default()).map_err(io::Error::other)?;
+        std::fs::create_dir_all(dir)?;
+        std::fs::write(path, text)
+    }
+
+    /// The options that override the config file.
+    pub fn cli_config(&self) -> ConfigFile {
+        ConfigFile {
+            poll_interval: self.poll_interval,
+            proxy: self.proxy.clone(),
+            api_url: self.api_url.clone(),
+            ..Default::default()
+        }
+    }
+
+    pub fn config(&self) -> (ConfigFile, Vec<String>) {
+        let (config, errors) = ConfigFile::load();
+        (config.merge(&self.cli_config()), errors)
+    }
+
+    pub fn name_and_domain(&self) -> (Option<String>, Option<String>) {
+        match &self.address {
+            Some(Address { name, domain }) => (Some(name.clone()), Some(domain.clone())),
+            None => (self.name.clone(), self.domain.clone()),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Address {
+    pub name: String,
+    pub domain: String,
+}
+
+impl FromStr for Address {
+    type Err = String;
+
+    fn from_str(address: &str) -> Result<Self, Self::Err> {
+        match address.split_once('@') {
+            Some((name, domain)) if !name.is_empty() && !domain.is_empty() => Ok(Self {
+                name: name.into(),
+                domain: domain.into(),
+            }),
+            _ => Err(format!("`{address}` is not of the form name@domain")),
+        }
+    }
+}
+
+fn valid_api_url(url: &str) -> Result<String, String> {
+    parse_api_url(url).map(|_| url.trim().to_owned())
+}
+
+fn parse_profile(profile: &str) -> Result<String, String> {
+    let valid = !profile.is_empty()
+        && profile
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    match valid {
+        true => Ok(profile.into()),
+        false => Err("profile names may only contain letters, digits, `-` and `_`".into()),
+    }
+}
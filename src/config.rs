@@ -1,29 +1,398 @@
-use eframe::egui::{Context, FontId, TextStyle};
-
-pub fn set_styles(ctx: &Context) {
-    let mut style = (*ctx.style()).clone();
-    style.text_styles = [
-        (
-            TextStyle::Heading,
-            FontId::new(30., eframe::egui::FontFamily::Proportional),
-        ),
-        (
-            TextStyle::Body,
-            FontId::new(18., eframe::egui::FontFamily::Monospace),
-        ),
-        (
-            TextStyle::Button,
-            FontId::new(22., eframe::egui::FontFamily::Monospace),
-        ),
-        (
-            TextStyle::Small,
-            FontId::new(14., eframe::egui::FontFamily::Monospace),
-        ),
-        (
-            TextStyle::Monospace,
-            FontId::new(16., eframe::egui::FontFamily::Monospace),
-        ),
-    ]
-    .into();
-    ctx.set_style(style);
+use eframe::egui::{
+    Color32, Context, FontFamily, FontId, Spacing, Stroke, TextStyle, Theme, ThemePreference,
+    Visuals,
+};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use tmgui::{
+    dates::{DateFormat, DateFormatter, TimeZoneChoice},
+    event_handler::API_URL,
+    i18n::{self, Language, tr},
+    rules::Rule,
+};
+
+use file::ConfigFile;
+
+pub mod file;
+mod fonts;
+pub mod state;
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ThemeChoice {
+    Light,
+    Dark,
+    System,
+    HighContrast,
+}
+
+impl ThemeChoice {
+    pub const ALL: [Self; 4] = [Self::Light, Self::Dark, Self::System, Self::HighContrast];
+
+    pub fn name(&self) -> String {
+        match self {
+            Self::Light => tr("theme_light"),
+            Self::Dark => tr("theme_dark"),
+            Self::System => tr("theme_system"),
+            Self::HighContrast => tr("theme_high_contrast"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum FontChoice {
+    Proportional,
+    Monospace,
+}
+
+impl FontChoice {
+    pub const ALL: [Self; 2] = [Self::Proportional, Self::Monospace];
+
+    pub fn name(&self) -> String {
+        match self {
+            Self::Proportional => tr("font_proportional"),
+            Self::Monospace => tr("font_monospace"),
+        }
+    }
+
+    pub fn family(&self) -> FontFamily {
+        match self {
+            Self::Proportional => FontFamily::Proportional,
+            Self::Monospace => FontFamily::Monospace,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Density {
+    Compact,
+    Comfortable,
+    Spacious,
+}
+
+impl Density {
+    pub const ALL: [Self; 3] = [Self::Compact, Self::Comfortable, Self::Spacious];
+
+    pub fn name(&self) -> String {
+        match self {
+            Self::Compact => tr("density_compact"),
+            Self::Comfortable => tr("density_comfortable"),
+            Self::Spacious => tr("density_spacious"),
+        }
+    }
+
+    fn factor(&self) -> f32 {
+        match self {
+            Self::Compact => 0.5,
+            Self::Comfortable => 1.,
+            Self::Spacious => 1.6,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub theme: ThemeChoice,
+    pub ui_scale: f32,
+    pub font: FontChoice,
+    pub heading_size: f32,
+    pub body_size: f32,
+    pub button_size: f32,
+    pub small_size: f32,
+    pub monospace_size: f32,
+    pub email_font: FontChoice,
+    pub email_font_size: f32,
+    pub density: Density,
+    pub font_files: Vec<PathBuf>,
+    pub date_format: DateFormat,
+    pub custom_date_format: String,
+    pub timezone: TimeZoneChoice,
+    pub timezone_name: String,
+    pub language: Option<Language>,
+    pub poll_interval_secs: u64,
+    pub ipc_enabled: bool,
+    pub ipc_port: u16,
+    pub default_domain: Option<String>,
+    pub proxy: String,
+    /// Empty for [`API_URL`].
+    pub api_url: String,
+    pub download_dir: Option<PathBuf>,
+    /// Minutes without input before an encrypted state locks, 0 for never.
+    pub auto_lock_minutes: u32,
+    pub rules: Vec<Rule>,
+    /// Run for each new email, see [`crate::hooks`].
+    pub hook_command: String,
+    pub hook_url: String,
+    /// Removes `utm_*` and similar parameters from opened links.
+    pub strip_tracking: bool,
+    /// Attachments larger than this are not downloaded, 0 for no limit.
+    pub max_download_mb: u32,
+    /// Values from the config file, environment and command line. They are
+    /// used over the fields above but never saved, so removing one from the
+    /// config file brings back the stored value.
+    #[serde(skip)]
+    pub overlay: ConfigFile,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            theme: ThemeChoice::System,
+            ui_scale: 1.,
+            font: FontChoice::Monospace,
+            heading_size: 30.,
+            body_size: 18.,
+            button_size: 22.,
+            small_size: 14.,
+            monospace_size: 16.,
+            email_font: FontChoice::Monospace,
+            email_font_size: 18.,
+            density: Density::Comfortable,
+            font_files: Default::default(),
+            date_format: DateFormat::Default,
+            custom_date_format: "%Y-%m-%d %H:%M:%S".into(),
+            timezone: TimeZoneChoice::Local,
+            timezone_name: "UTC".into(),
+            language: None,
+            poll_interval_secs: 0,
+            ipc_enabled: false,
+            ipc_port: 7337,
+            default_domain: None,
+            proxy: String::new(),
+            api_url: String::new(),
+            download_dir: None,
+            auto_lock_minutes: 10,
+            rules: Vec::new(),
+            hook_command: String::new(),
+            hook_url: String::new(),
+            strip_tracking: true,
+            max_download_mb: 25,
+            overlay: ConfigFile::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn email_font_id(&self) -> FontId {
+        FontId::new(self.email_font_size, self.email_font.family())
+    }
+
+    pub fn date_formatter(&self) -> DateFormatter<'_> {
+        DateFormatter {
+            format: self.date_format,
+            custom: &self.custom_date_format,
+            timezone: self.timezone,
+            timezone_name: &self.timezone_name,
+        }
+    }
+
+    /// Takes over how the app looks: theme, scale, fonts, density, date
+    /// format and language.
+    pub fn copy_appearance(&mut self, from: &Self) {
+        self.theme = from.theme;
+        self.ui_scale = from.ui_scale;
+        self.font = from.font;
+        self.heading_size = from.heading_size;
+        self.body_size = from.body_size;
+        self.button_size = from.button_size;
+        self.small_size = from.small_size;
+        self.monospace_size = from.monospace_size;
+        self.email_font = from.email_font;
+        self.email_font_size = from.email_font_size;
+        self.density = from.density;
+        self.font_files = from.font_files.clone();
+        self.date_format = from.date_format;
+        self.custom_date_format = from.custom_date_format.clone();
+        self.timezone = from.timezone;
+        self.timezone_name = from.timezone_name.clone();
+        self.language = from.language;
+    }
+
+    /// Takes over what teammates share in an exported file: the appearance,
+    /// the poll interval and the API URL.
+    pub fn copy_shared(&mut self, from: &Self) {
+        self.copy_appearance(from);
+        self.poll_interval_secs = from.poll_interval_secs;
+        self.api_url = from.api_url.clone();
+    }
+
+    /// The settings that run commands, reach other hosts or write files and
+    /// that an imported file sets differently. They are only taken over
+    /// after asking, see [`Self::copy_sensitive`].
+    pub fn sensitive_changes(&self, imported: &Self) -> Vec<&'static str> {
+        let defaults = Self::default();
+        let changes = [
+            (
+                "proxy",
+                imported.proxy != defaults.proxy && imported.proxy != self.proxy,
+            ),
+            (
+                "download_dir",
+                imported.download_dir.is_some() && imported.download_dir != self.download_dir,
+            ),
+            ("ipc_enabled", imported.ipc_enabled && !self.ipc_enabled),
+            (
+                "ipc_port",
+                imported.ipc_port != defaults.ipc_port && imported.ipc_port != self.ipc_port,
+            ),
+            (
+                "hook_command",
+                !imported.hook_command.is_empty() && imported.hook_command != self.hook_command,
+            ),
+            (
+                "hook_url",
+                !imported.hook_url.is_empty() && imported.hook_url != self.hook_url,
+            ),
+            (
+                "rules",
+                !imported.rules.is_empty() && imported.rules != self.rules,
+            ),
+        ];
+        changes
+            .into_iter()
+            .filter(|(_, changed)| *changed)
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    /// Takes over the sensitive settings an imported file sets. Those it
+    /// leaves at their defaults are kept.
+    pub fn copy_sensitive(&mut self, imported: &Self) {
+        let defaults = Self::default();
+        if imported.proxy != defaults.proxy {
+            self.proxy = imported.proxy.clone();
+        }
+        if imported.download_dir.is_some() {
+            self.download_dir = imported.download_dir.clone();
+        }
+        self.ipc_enabled |= imported.ipc_enabled;
+        if imported.ipc_port != defaults.ipc_port {
+            self.ipc_port = imported.ipc_port;
+        }
+        if !imported.hook_command.is_empty() {
+            self.hook_command = imported.hook_command.clone();
+        }
+        if !imported.hook_url.is_empty() {
+            self.hook_url = imported.hook_url.clone();
+        }
+        if !imported.rules.is_empty() {
+            self.rules = imported.rules.clone();
+        }
+    }
+
+    pub fn proxy(&self) -> &str {
+        self.overlay.proxy.as_deref().unwrap_or(&self.proxy)
+    }
+
+    pub fn api_url(&self) -> &str {
+        match self.overlay.api_url.as_deref().unwrap_or(&self.api_url) {
+            "" => API_URL,
+            url => url,
+        }
+    }
+
+    pub fn poll_interval_secs(&self) -> u64 {
+        match self.overlay.poll_interval {
+            Some(interval) => interval.as_secs(),
+            None => self.poll_interval_secs,
+        }
+    }
+
+    pub fn default_domain(&self) -> Option<String> {
+        self.overlay
+            .default_domain
+            .clone()
+            .or(self.default_domain.clone())
+    }
+
+    pub fn download_dir(&self) -> PathBuf {
+        self.overlay
+            .download_dir
+            .clone()
+            .or(self.download_dir.clone())
+            .or_else(dirs::download_dir)
+            .unwrap_or_else(|| ".".into())
+    }
+
+    /// [`Self::max_download_mb`] in bytes, 0 for no limit.
+    pub fn max_download_bytes(&self) -> u64 {
+        u64::from(self.max_download_mb) * 1024 * 1024
+    }
+
+    pub fn attachment_url(&self, id: &str) -> String {
+        format!("{}/attachments/{id}", self.api_url().trim_end_matches('/'))
+    }
+}
+
+pub fn apply_settings(ctx: &Context, settings: &Settings, previous: Option<&Settings>) {
+    if previous.is_none_or(|previous| previous.font_files != settings.font_files) {
+        fonts::load_fonts(ctx, &settings.font_files);
+    }
+    i18n::set_language(settings.language.unwrap_or_else(Language::system));
+    ctx.set_theme(match settings.theme {
+        ThemeChoice::Light => ThemePreference::Light,
+        ThemeChoice::Dark | ThemeChoice::HighContrast => ThemePreference::Dark,
+        ThemeChoice::System => ThemePreference::System,
+    });
+    ctx.set_visuals_of(Theme::Light, Visuals::light());
+    ctx.set_visuals_of(
+        Theme::Dark,
+        match settings.theme {
+            ThemeChoice::HighContrast => high_contrast_visuals(),
+            _ => Visuals::dark(),
+        },
+    );
+    ctx.set_zoom_factor(settings.ui_scale);
+    ctx.all_styles_mut(|style| {
+        let family = settings.font.family();
+        style.text_styles = [
+            (
+                TextStyle::Heading,
+                FontId::new(settings.heading_size, FontFamily::Proportional),
+            ),
+            (
+                TextStyle::Body,
+                FontId::new(settings.body_size, family.clone()),
+            ),
+            (
+                TextStyle::Button,
+                FontId::new(settings.button_size, family.clone()),
+            ),
+            (TextStyle::Small, FontId::new(settings.small_size, family)),
+            (
+                TextStyle::Monospace,
+                FontId::new(settings.monospace_size, FontFamily::Monospace),
+            ),
+        ]
+        .into();
+
+        let factor = settings.density.factor();
+        let spacing = Spacing::default();
+        style.spacing.item_spacing = spacing.item_spacing * factor;
+        style.spacing.button_padding = spacing.button_padding * factor;
+    });
+}
+
+fn high_contrast_visuals() -> Visuals {
+    let mut visuals = Visuals::dark();
+    visuals.override_text_color = Some(Color32::WHITE);
+    visuals.panel_fill = Color32::BLACK;
+    visuals.window_fill = Color32::BLACK;
+    visuals.extreme_bg_color = Color32::BLACK;
+    visuals.faint_bg_color = Color32::from_gray(24);
+    visuals.hyperlink_color = Color32::YELLOW;
+    visuals.selection.bg_fill = Color32::from_rgb(0, 90, 200);
+    visuals.selection.stroke = Stroke::new(1., Color32::WHITE);
+    for widget in [
+        &mut visuals.widgets.noninteractive,
+        &mut visuals.widgets.inactive,
+        &mut visuals.widgets.hovered,
+        &mut visuals.widgets.active,
+        &mut visuals.widgets.open,
+    ] {
+        widget.bg_stroke = Stroke::new(1., Color32::WHITE);
+        widget.fg_stroke = Stroke::new(1.5, Color32::WHITE);
+    }
+    visuals
 }
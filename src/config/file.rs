@@ -0,0 +1,194 @@
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    time::{Duration, Instant, SystemTime},
+};
+
+use eframe::egui::{Key, KeyboardShortcut, Modifiers};
+use serde::Deserialize;
+use tmgui::event_handler::{parse_api_url, parse_proxy};
+
+/// Overrides the location of the config file.
+pub const CONFIG_ENV: &str = "TMGUI_CONFIG";
+pub const CONFIG_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Preferences from `config.toml` and `TMGUI_*` variables, which take
+/// precedence over the settings stored by the app but are never saved into
+/// them, see [`super::Settings::overlay`].
+#[derive(Clone, Default, PartialEq)]
+pub struct ConfigFile {
+    pub default_domain: Option<String>,
+    pub poll_interval: Option<Duration>,
+    pub proxy: Option<String>,
+    pub api_url: Option<String>,
+    pub download_dir: Option<PathBuf>,
+    /// Action names with their shortcuts.
+    pub shortcuts: Vec<(String, KeyboardShortcut)>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawConfig {
+    default_domain: Option<String>,
+    poll_interval: Option<String>,
+    proxy: Option<String>,
+    api_url: Option<String>,
+    download_dir: Option<PathBuf>,
+    #[serde(default)]
+    shortcuts: BTreeMap<String, String>,
+}
+
+impl ConfigFile {
+    pub fn path() -> Option<PathBuf> {
+        match std::env::var_os(CONFIG_ENV) {
+            Some(path) => Some(path.into()),
+            None => Some(dirs::config_dir()?.join("tmgui").join("config.toml")),
+        }
+    }
+
+    /// Reads the file and the environment. Invalid values are skipped and
+    /// described in the returned errors.
+    pub fn load() -> (Self, Vec<String>) {
+        let mut errors = Vec::new();
+        let raw = match Self::path().map(std::fs::read_to_string) {
+            Some(Ok(text)) => toml::from_str(&text).unwrap_or_else(|e| {
+                errors.push(format!("config.toml: {}", e.message()));
+                RawConfig::default()
+            }),
+            _ => RawConfig::default(),
+        };
+        let env = |name: &str| std::env::var(format!("TMGUI_{name}")).ok();
+        let raw = RawConfig {
+            default_domain: env("DEFAULT_DOMAIN").or(raw.default_domain),
+            poll_interval: env("POLL_INTERVAL").or(raw.poll_interval),
+            proxy: env("PROXY").or(raw.proxy),
+            api_url: env("API_URL").or(raw.api_url),
+            download_dir: env("DOWNLOAD_DIR").map(PathBuf::from).or(raw.download_dir),
+            shortcuts: raw.shortcuts,
+        };
+        let config = Self::validate(raw, &mut errors);
+        (config, errors)
+    }
+
+    /// Values set in `other` replace the ones in `self`.
+    pub fn merge(mut self, other: &Self) -> Self {
+        let other = other.clone();
+        self.default_domain = other.default_domain.or(self.default_domain);
+        self.poll_interval = other.poll_interval.or(self.poll_interval);
+        self.proxy = other.proxy.or(self.proxy);
+        self.api_url = other.api_url.or(self.api_url);
+        self.download_dir = other.download_dir.or(self.download_dir);
+        self.shortcuts.extend(other.shortcuts);
+        self
+    }
+
+    fn validate(raw: RawConfig, errors: &mut Vec<String>) -> Self {
+        let mut check = |field: &str, result: Result<(), String>| match result {
+            Ok(()) => true,
+            Err(e) => {
+                errors.push(format!("{field}: {e}"));
+                false
+            }
+        };
+        let proxy = raw
+            .proxy
+            .filter(|url| check("proxy", parse_proxy(url).map(|_| ())));
+        let api_url = raw
+            .api_url
+            .filter(|url| check("api_url", parse_api_url(url).map(|_| ())));
+        let default_domain = raw.default_domain.filter(|domain| {
+            let valid = !domain.is_empty() && !domain.contains(['@', '/', ' ']);
+            check(
+                "default_domain",
+                valid.then_some(()).ok_or("not a domain".into()),
+            )
+        });
+        let poll_interval =
+            raw.poll_interval
+                .and_then(|interval| match parse_poll_interval(&interval) {
+                    Ok(interval) => Some(interval),
+                    Err(e) => {
+                        check("poll_interval", Err(e));
+                        None
+                    }
+                });
+        let shortcuts = raw
+            .shortcuts
+            .into_iter()
+            .filter_map(|(action, shortcut)| match parse_shortcut(&shortcut) {
+                Some(parsed) => Some((action, parsed)),
+                None => {
+                    check(
+                        &format!("shortcuts.{action}"),
+                        Err(format!("`{shortcut}` is not a shortcut like Ctrl+Shift+R")),
+                    );
+                    None
+                }
+            })
+            .collect();
+        Self {
+            default_domain,
+            poll_interval,
+            proxy,
+            api_url,
+            download_dir: raw.download_dir,
+            shortcuts,
+        }
+    }
+}
+
+/// Notices changes to the config file by its modification time.
+pub struct ConfigWatcher {
+    modified: Option<SystemTime>,
+    last_check: Instant,
+}
+
+impl ConfigWatcher {
+    pub fn new() -> Self {
+        Self {
+            modified: Self::modified(),
+            last_check: Instant::now(),
+        }
+    }
+
+    pub fn changed(&mut self) -> bool {
+        if self.last_check.elapsed() < CONFIG_CHECK_INTERVAL {
+            return false;
+        }
+        self.last_check = Instant::now();
+        let modified = Self::modified();
+        let changed = modified != self.modified;
+        self.modified = modified;
+        changed
+    }
+
+    fn modified() -> Option<SystemTime> {
+        std::fs::metadata(ConfigFile::path()?).ok()?.modified().ok()
+    }
+}
+
+/// Polling works in whole seconds, so shorter intervals are refused rather
+/// than rounded down to 0, which would turn polling off.
+pub fn parse_poll_interval(interval: &str) -> Result<Duration, String> {
+    let interval = humantime::parse_duration(interval).map_err(|e| e.to_string())?;
+    match interval < Duration::from_secs(1) {
+        true => Err("must be at least 1s".into()),
+        false => Ok(interval),
+    }
+}
+
+fn parse_shortcut(shortcut: &str) -> Option<KeyboardShortcut> {
+    let mut modifiers = Modifiers::NONE;
+    let mut parts = shortcut.split('+').map(str::trim).collect::<Vec<_>>();
+    let key = Key::from_name(parts.pop()?)?;
+    for part in parts {
+        modifiers |= match part.to_lowercase().as_str() {
+            "ctrl" | "control" => Modifiers::CTRL,
+            "cmd" | "command" => Modifiers::COMMAND,
+            "alt" | "option" => Modifiers::ALT,
+            "shift" => Modifiers::SHIFT,
+            _ => return None,
+        };
+    }
+    Some(KeyboardShortcut::new(modifiers, key))
+}
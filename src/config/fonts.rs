@@ -0,0 +1,127 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use eframe::egui::{Context, FontData, FontDefinitions, FontFamily};
+use log::{info, warn};
+
+/// Arabic, Hebrew, Cyrillic, Greek and many symbols for machines without the
+/// system fonts below. CJK fonts are too large to bundle.
+const BUNDLED_FONT: &[u8] = include_bytes!("../../assets/fonts/DejaVuSans.ttf");
+
+/// Counts font loads, so a slow one does not replace a newer one.
+static GENERATION: Mutex<usize> = Mutex::new(0);
+
+// One entry per script group; the first file found in each group is used.
+const FALLBACK_FONTS: &[(&str, &[&str])] = &[
+    (
+        "cjk",
+        &[
+            "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+            "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+            "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+            "/usr/share/fonts/truetype/droid/DroidSansFallbackFull.ttf",
+            "/usr/share/fonts/wenquanyi/wqy-microhei/wqy-microhei.ttc",
+            "/System/Library/Fonts/Hiragino Sans GB.ttc",
+            "/System/Library/Fonts/PingFang.ttc",
+            "C:\\Windows\\Fonts\\msyh.ttc",
+            "C:\\Windows\\Fonts\\YuGothM.ttc",
+        ],
+    ),
+    (
+        "arabic",
+        &[
+            "/usr/share/fonts/truetype/noto/NotoSansArabic-Regular.ttf",
+            "/usr/share/fonts/noto/NotoSansArabic-Regular.ttf",
+            "/usr/share/fonts/google-noto/NotoSansArabic-Regular.ttf",
+            "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+            "/System/Library/Fonts/GeezaPro.ttc",
+            "C:\\Windows\\Fonts\\arial.ttf",
+        ],
+    ),
+    (
+        "hebrew",
+        &[
+            "/usr/share/fonts/truetype/noto/NotoSansHebrew-Regular.ttf",
+            "/usr/share/fonts/noto/NotoSansHebrew-Regular.ttf",
+            "/usr/share/fonts/google-noto/NotoSansHebrew-Regular.ttf",
+            "/System/Library/Fonts/ArialHB.ttc",
+        ],
+    ),
+    (
+        "symbols",
+        &[
+            "/usr/share/fonts/truetype/noto/NotoSansSymbols2-Regular.ttf",
+            "/usr/share/fonts/noto/NotoSansSymbols2-Regular.ttf",
+            "/usr/share/fonts/truetype/ancient-scripts/Symbola_hint.ttf",
+            "/System/Library/Fonts/Apple Symbols.ttf",
+            "C:\\Windows\\Fonts\\seguisym.ttf",
+        ],
+    ),
+];
+
+/// Shows the bundled font at once and adds the user's and the system's fonts
+/// once they are read on a thread, as CJK collections take several MB.
+pub fn load_fonts(ctx: &Context, font_files: &[PathBuf]) {
+    let generation = {
+        let mut generation = GENERATION.lock().unwrap();
+        *generation += 1;
+        ctx.set_fonts(definitions(Vec::new()));
+        *generation
+    };
+    let ctx = ctx.clone();
+    let font_files = font_files.to_vec();
+    thread::spawn(move || {
+        let fonts = read_fonts(&font_files);
+        let current = GENERATION.lock().unwrap();
+        if *current == generation {
+            ctx.set_fonts(definitions(fonts));
+            ctx.request_repaint();
+        }
+    });
+}
+
+fn read_fonts(font_files: &[PathBuf]) -> Vec<(String, FontData)> {
+    let user_fonts = font_files
+        .iter()
+        .enumerate()
+        .filter_map(|(index, path)| match std::fs::read(path) {
+            Ok(bytes) => Some((format!("user-{index}"), FontData::from_owned(bytes))),
+            Err(e) => {
+                warn!("Could not load font {}: {e:?}", path.display());
+                None
+            }
+        });
+    let system_fonts = FALLBACK_FONTS.iter().filter_map(|(name, candidates)| {
+        candidates
+            .iter()
+            .find_map(|path| system_font(Path::new(path)))
+            .map(|data| (format!("fallback-{name}"), data))
+    });
+    user_fonts.chain(system_fonts).collect()
+}
+
+/// The default fonts, then these, then the bundled one.
+fn definitions(fonts: Vec<(String, FontData)>) -> FontDefinitions {
+    let mut definitions = FontDefinitions::default();
+    let bundled = ("bundled".to_owned(), FontData::from_static(BUNDLED_FONT));
+    for (name, data) in fonts.into_iter().chain([bundled]) {
+        definitions.font_data.insert(name.clone(), Arc::new(data));
+        for family in [FontFamily::Proportional, FontFamily::Monospace] {
+            definitions
+                .families
+                .entry(family)
+                .or_default()
+                .push(name.clone());
+        }
+    }
+    definitions
+}
+
+fn system_font(path: &Path) -> Option<FontData> {
+    let bytes = std::fs::read(path).ok()?;
+    info!("Loaded fallback font {}", path.display());
+    Some(FontData::from_owned(bytes))
+}
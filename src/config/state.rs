@@ -0,0 +1,261 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use eframe::egui::{self, ViewportBuilder, ViewportCommand, ViewportInfo, vec2};
+use log::warn;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use tmgui::{
+    forward::Forwarding,
+    retention::Retention,
+    vault::{Vault, VaultKey},
+};
+
+use super::Settings;
+use crate::{cli::APP_ID, ipc::IpcServer};
+
+pub const STATE_FILE: &str = "app.ron";
+/// Where a state file that could not be read is moved.
+pub const STATE_BACKUP: &str = "app.ron.bak";
+pub const STATE_KEY: &str = "state";
+/// Below the storage directory, holds one directory per `--profile`.
+pub const PROFILES_DIR: &str = "profiles";
+/// Bump when a field changes meaning and add the conversion to `migrate`.
+pub const STATE_VERSION: u32 = 1;
+const RECENT_ADDRESSES: usize = 20;
+
+/// Everything tmgui keeps between runs, stored under [`STATE_KEY`].
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SavedState {
+    pub version: u32,
+    pub name: Option<String>,
+    pub domain: Option<String>,
+    pub recent_addresses: Vec<String>,
+    /// Retention rules by address.
+    pub retention: BTreeMap<String, Retention>,
+    /// SMTP forwarding by address.
+    pub forwarding: BTreeMap<String, Forwarding>,
+    pub settings: Settings,
+    pub window: WindowLayout,
+    /// The sealed [`Vault`] once a passphrase is set, the address fields
+    /// are empty then.
+    pub vault: Option<String>,
+}
+
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowLayout {
+    pub size: Option<[f32; 2]>,
+    pub maximized: bool,
+}
+
+impl Default for SavedState {
+    fn default() -> Self {
+        Self {
+            version: STATE_VERSION,
+            name: None,
+            domain: None,
+            recent_addresses: Vec::new(),
+            retention: BTreeMap::new(),
+            forwarding: BTreeMap::new(),
+            settings: Settings::default(),
+            window: WindowLayout::default(),
+            vault: None,
+        }
+    }
+}
+
+impl SavedState {
+    /// Reads the state from storage, converting the loose keys written
+    /// before the state was versioned.
+    pub fn load(stored: impl Fn(&str) -> Option<String>) -> Result<Self, String> {
+        match stored(STATE_KEY) {
+            Some(text) => Self::parse(&text),
+            None => Ok(Self::from_legacy(stored)),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        #[derive(Deserialize)]
+        struct Version {
+            #[serde(default)]
+            version: u32,
+        }
+        let Version { version } = ron::from_str(text).map_err(|e| e.to_string())?;
+        let state = ron::from_str(text).map_err(|e| e.to_string())?;
+        Ok(migrate(version, state))
+    }
+
+    pub fn to_ron(&self) -> String {
+        ron::ser::to_string_pretty(self, PrettyConfig::default()).unwrap_or_default()
+    }
+
+    /// Writes the shared settings, see [`Settings::copy_shared`], and the
+    /// window layout. Addresses, the proxy, hooks, rules and other settings
+    /// stay private.
+    pub fn export(&self, path: &Path) -> Result<(), String> {
+        let mut settings = Settings::default();
+        settings.copy_shared(&self.settings);
+        let shared = Self {
+            settings,
+            window: self.window.clone(),
+            ..Default::default()
+        };
+        std::fs::write(path, shared.to_ron())
+            .map_err(|e| format!("Could not export to {path:?}: {e}"))
+    }
+
+    /// Takes the shared settings and window layout from an exported file,
+    /// and its sensitive settings too if `sensitive` is set. Returns
+    /// the sensitive settings the file sets differently, see
+    /// [`Settings::sensitive_changes`].
+    pub fn import(&mut self, path: &Path, sensitive: bool) -> Result<Vec<&'static str>, String> {
+        let text =
+            std::fs::read_to_string(path).map_err(|e| format!("Could not import {path:?}: {e}"))?;
+        let imported = Self::parse(&text).map_err(|e| format!("Could not import {path:?}: {e}"))?;
+        self.settings.copy_shared(&imported.settings);
+        self.window = imported.window;
+        let changes = self.settings.sensitive_changes(&imported.settings);
+        if sensitive {
+            self.settings.copy_sensitive(&imported.settings);
+        }
+        Ok(changes)
+    }
+
+    /// Moves the addresses into the encrypted vault.
+    pub fn seal(&mut self, key: &VaultKey) {
+        let vault = Vault {
+            name: self.name.take(),
+            domain: self.domain.take(),
+            recent_addresses: std::mem::take(&mut self.recent_addresses),
+            retention: std::mem::take(&mut self.retention),
+            forwarding: std::mem::take(&mut self.forwarding),
+        };
+        self.vault = Some(vault.seal(key));
+    }
+
+    /// Decrypts the vault into the address fields, keeping an address that
+    /// is already set. Returns the key to seal it again, or `None` if the
+    /// state is not encrypted.
+    pub fn unlock(&mut self, passphrase: &str) -> Result<Option<VaultKey>, String> {
+        let Some(sealed) = &self.vault else {
+            return Ok(None);
+        };
+        let (vault, key) = Vault::open(sealed, passphrase)?;
+        self.name = self.name.take().or(vault.name);
+        self.domain = self.domain.take().or(vault.domain);
+        self.recent_addresses = vault.recent_addresses;
+        self.retention = vault.retention;
+        self.forwarding = vault.forwarding;
+        self.vault = None;
+        Ok(Some(key))
+    }
+
+    pub fn remember_address(&mut self, address: String) {
+        self.recent_addresses.retain(|recent| *recent != address);
+        self.recent_addresses.insert(0, address);
+        self.recent_addresses.truncate(RECENT_ADDRESSES);
+    }
+
+    fn from_legacy(stored: impl Fn(&str) -> Option<String>) -> Self {
+        let settings = stored("settings")
+            .and_then(|settings| ron::from_str(&settings).ok())
+            .unwrap_or_default();
+        let mut state = Self {
+            name: stored("name"),
+            domain: stored("domain"),
+            settings,
+            ..Default::default()
+        };
+        if let (Some(name), Some(domain)) = (&state.name, &state.domain) {
+            state.remember_address([name.as_str(), domain].join("@"));
+        }
+        state
+    }
+}
+
+/// Where attachments are downloaded to be opened.
+pub fn cache_dir() -> Option<PathBuf> {
+    Some(dirs::cache_dir()?.join("tmgui"))
+}
+
+/// Creates the [`cache_dir`] with access for the user only.
+pub fn create_cache_dir() -> io::Result<PathBuf> {
+    let dir = cache_dir().ok_or_else(|| io::Error::other("No cache directory"))?;
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(&dir)?;
+    // It may be left from a version that created it with the defaults.
+    #[cfg(unix)]
+    fs::set_permissions(&dir, std::os::unix::fs::PermissionsExt::from_mode(0o700))?;
+    Ok(dir)
+}
+
+/// Deletes everything tmgui keeps on disk and the cached attachments. That
+/// includes the other profiles, as any of them tells which addresses were
+/// used.
+pub fn wipe(profile_dir: Option<&Path>) -> io::Result<()> {
+    // Older versions cached attachments in the shared temporary directory.
+    let mut paths = vec![std::env::temp_dir().join("tmgui")];
+    paths.extend(cache_dir());
+    let storage_dir = eframe::storage_dir(APP_ID);
+    for dir in profile_dir.iter().copied().chain(storage_dir.as_deref()) {
+        paths.extend([
+            dir.join(STATE_FILE),
+            dir.join(STATE_BACKUP),
+            IpcServer::token_path(dir),
+        ]);
+    }
+    paths.extend(storage_dir.map(|dir| dir.join(PROFILES_DIR)));
+    for path in paths {
+        let removed = match path.is_dir() {
+            true => std::fs::remove_dir_all(&path),
+            false => std::fs::remove_file(&path),
+        };
+        match removed {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn migrate(version: u32, mut state: SavedState) -> SavedState {
+    if version > STATE_VERSION {
+        warn!("The saved state is from a newer version ({version}), unknown fields are ignored");
+    }
+    state.version = STATE_VERSION;
+    state
+}
+
+impl WindowLayout {
+    pub fn update(&mut self, viewport: &ViewportInfo) {
+        self.maximized = viewport.maximized.unwrap_or(false);
+        if !self.maximized
+            && let Some(rect) = viewport.inner_rect
+        {
+            self.size = Some([rect.width(), rect.height()]);
+        }
+    }
+
+    pub fn builder(&self, builder: ViewportBuilder) -> ViewportBuilder {
+        let builder = builder.with_maximized(self.maximized);
+        match self.size {
+            Some([width, height]) => builder.with_inner_size([width, height]),
+            None => builder,
+        }
+    }
+
+    pub fn apply(&self, ctx: &egui::Context) {
+        if let Some([width, height]) = self.size {
+            ctx.send_viewport_cmd(ViewportCommand::InnerSize(vec2(width, height)));
+        }
+        ctx.send_viewport_cmd(ViewportCommand::Maximized(self.maximized));
+    }
+}
@@ -0,0 +1,162 @@
+//! Formatting of the Unix timestamps the API returns.
+
+use chrono::{
+    DateTime, FixedOffset, Local, NaiveDate, NaiveDateTime, SecondsFormat, Utc,
+    format::{Item, StrftimeItems},
+};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::{self, tr, tr_with};
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum DateFormat {
+    Default,
+    Relative,
+    Iso8601,
+    Custom,
+}
+
+impl DateFormat {
+    pub const ALL: [Self; 4] = [Self::Default, Self::Relative, Self::Iso8601, Self::Custom];
+
+    pub fn name(&self) -> String {
+        match self {
+            Self::Default => tr("date_format_default"),
+            Self::Relative => tr("date_format_relative"),
+            Self::Iso8601 => tr("date_format_iso"),
+            Self::Custom => tr("date_format_custom"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TimeZoneChoice {
+    Local,
+    Utc,
+    Named,
+}
+
+impl TimeZoneChoice {
+    pub const ALL: [Self; 3] = [Self::Local, Self::Utc, Self::Named];
+
+    pub fn name(&self) -> String {
+        match self {
+            Self::Local => tr("timezone_local"),
+            Self::Utc => tr("timezone_utc"),
+            Self::Named => tr("timezone_named"),
+        }
+    }
+}
+
+/// Formats timestamps in the current language.
+///
+/// `custom` is a strftime pattern used with [`DateFormat::Custom`] and
+/// `timezone_name` an IANA zone used with [`TimeZoneChoice::Named`].
+#[derive(Clone, Copy)]
+pub struct DateFormatter<'a> {
+    pub format: DateFormat,
+    pub custom: &'a str,
+    pub timezone: TimeZoneChoice,
+    pub timezone_name: &'a str,
+}
+
+impl DateFormatter<'_> {
+    /// The date as shown in lists and headers.
+    pub fn format(&self, timestamp: i64) -> String {
+        let Some(date) = self.zoned(timestamp) else {
+            return tr("date_invalid");
+        };
+        match self.format {
+            DateFormat::Default => localized(date, &tr("date_default_format")),
+            DateFormat::Relative => relative(date),
+            DateFormat::Iso8601 => date.to_rfc3339_opts(SecondsFormat::Secs, true),
+            DateFormat::Custom if valid_pattern(self.custom) => localized(date, self.custom),
+            DateFormat::Custom => tr("date_invalid_format"),
+        }
+    }
+
+    /// The long local date followed by the UTC timestamp.
+    pub fn full(&self, timestamp: i64) -> String {
+        match (
+            self.zoned(timestamp),
+            DateTime::from_timestamp(timestamp, 0),
+        ) {
+            (Some(date), Some(utc)) => format!(
+                "{}\n{}",
+                localized(date, &tr("date_full_format")),
+                utc.format("%Y-%m-%dT%H:%M:%SZ")
+            ),
+            _ => tr_with(
+                "date_invalid_timestamp",
+                &[("timestamp", &timestamp.to_string())],
+            ),
+        }
+    }
+
+    /// An appointment in the configured zone, never relative.
+    pub fn event(&self, timestamp: i64) -> String {
+        match self.zoned(timestamp) {
+            Some(date) => localized(date, &tr("date_default_format")),
+            None => tr("date_invalid"),
+        }
+    }
+
+    /// A date and time without a zone, as written.
+    pub fn wall_clock(&self, time: NaiveDateTime) -> String {
+        localized(time.and_utc().fixed_offset(), &tr("date_default_format"))
+    }
+
+    /// A whole day.
+    pub fn day(&self, date: NaiveDate) -> String {
+        let midnight = date.and_time(Default::default()).and_utc().fixed_offset();
+        localized(midnight, &tr("date_short_format"))
+    }
+
+    /// The timestamp in the configured zone, falling back to UTC for unknown
+    /// zone names.
+    pub fn zoned(&self, timestamp: i64) -> Option<DateTime<FixedOffset>> {
+        let date = DateTime::from_timestamp(timestamp, 0)?;
+        Some(match self.timezone {
+            TimeZoneChoice::Local => date.with_timezone(&Local).fixed_offset(),
+            TimeZoneChoice::Utc => date.fixed_offset(),
+            TimeZoneChoice::Named => match self.timezone_name.parse::<Tz>() {
+                Ok(tz) => date.with_timezone(&tz).fixed_offset(),
+                Err(_) => date.fixed_offset(),
+            },
+        })
+    }
+}
+
+pub fn valid_pattern(pattern: &str) -> bool {
+    !StrftimeItems::new(pattern).any(|item| item == Item::Error)
+}
+
+pub fn valid_timezone(name: &str) -> bool {
+    name.parse::<Tz>().is_ok()
+}
+
+/// The timestamp as RFC 3339 in UTC, or the raw number if it is out of range.
+pub fn iso_date(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp, 0)
+        .map(|date| date.to_rfc3339())
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+fn localized(date: DateTime<FixedOffset>, pattern: &str) -> String {
+    date.format_localized(pattern, i18n::language().locale())
+        .to_string()
+}
+
+fn relative(date: DateTime<FixedOffset>) -> String {
+    let seconds = Utc::now().signed_duration_since(date).num_seconds();
+    let count = |unit: i64| (seconds / unit).to_string();
+    match seconds {
+        s if s < 60 => tr("relative_just_now"),
+        s if s < 3600 => tr_with("relative_minutes", &[("count", &count(60))]),
+        s if s < 86400 => tr_with("relative_hours", &[("count", &count(3600))]),
+        s if s < 2 * 86400 => tr("relative_yesterday"),
+        s if s < 30 * 86400 => tr_with("relative_days", &[("count", &count(86400))]),
+        _ => localized(date, &tr("date_short_format")),
+    }
+}
@@ -1,138 +1,504 @@
-use std::sync::mpsc::{Receiver, Sender};
+//! The backend that runs API calls off the UI thread.
+//!
+//! [`Handler::spawn`] needs a Tokio runtime and answers every [`Event`] with
+//! one [`EventResponse`], in order. Requests use the proxies from the
+//! environment (`HTTPS_PROXY`, `NO_PROXY`, ...) until [`Event::SetProxy`]
+//! chooses one, and go to [`API_URL`] until [`Event::SetApiUrl`] chooses
+//! another.
 
+use std::{
+    cell::RefCell,
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::mpsc::{Receiver, Sender, channel},
+};
+
+use email_address::EmailAddress;
 use log::error;
-use tmapi::{Attachment, Client, Email};
-use tokio::runtime::Handle;
+use reqwest::{Method, NoProxy, Proxy, Url};
+use serde::{Deserialize, de::DeserializeOwned, de::IgnoredAny};
+use tmapi::{Attachment, Email};
+use tokio::{runtime::Handle, task::spawn_blocking};
+
+use crate::units::UnitSize;
 
+/// A request to the API. Email addresses are passed as `name@domain`.
 pub enum Event {
     DeleteAllEmails(String),
-    DeleteEmail(String, usize),
+    /// Deletes an email by id, which is echoed back in the response.
+    DeleteEmail(String),
     FetchEmails(String),
+    /// Fetches the page of emails after this offset.
     FetchMoreEmails(String, u32),
     FetchEmail(String),
     FetchDomanins,
     CountEmails(String),
     GetAttachments(String),
+    /// Downloads an attachment by id into this file. The download stops and
+    /// the file is deleted once it grows past the limit in bytes, 0 for
+    /// none.
+    DownloadAttachment(String, PathBuf, u64),
+    /// Sends all later requests through this proxy URL, or the proxies from
+    /// the environment if it is empty.
+    SetProxy(String),
+    /// Sends all later requests to the API at this URL, see
+    /// [`parse_api_url`].
+    SetApiUrl(String),
 }
 
+/// The result of an [`Event`].
 pub enum EventResponse {
     Domains(Vec<String>),
-    Emails(Vec<Email>),
+    /// The first page of emails of an address.
+    Emails(String, Vec<Email>),
     EmailsMore(Vec<Email>),
     Email(Email),
     Count(u32),
     EmailsDeleted,
-    EmailDeleted(usize),
+    EmailDeleted(String),
     Attachments(Vec<Attachment>),
+    AttachmentSaved(PathBuf),
+    ProxySet,
+    ApiUrlSet,
+    /// Any event can fail with a message instead of its usual response.
+    Error(String),
 }
 
+/// Executes [`Event`]s and sends back their [`EventResponse`]s.
 pub struct Handler {
     event_stream: Receiver<Event>,
     response_stream: Sender<EventResponse>,
+    http: RefCell<reqwest::Client>,
+    api: RefCell<Url>,
+}
+
+/// The barid.site API, used unless another is configured.
+pub const API_URL: &str = "https://api.barid.site";
+
+/// How the API answers. The domains come without `success`.
+#[derive(Deserialize)]
+struct Answer<T> {
+    #[serde(default = "succeeded")]
+    success: bool,
+    result: Option<T>,
+    error: Option<AnswerError>,
+}
+
+#[derive(Deserialize)]
+struct AnswerError {
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct Count {
+    count: u32,
+}
+
+fn succeeded() -> bool {
+    true
+}
+
+/// Parses the URL of an API that works like the one at [`API_URL`], e.g. a
+/// self-hosted instance or a mock server for tests.
+pub fn parse_api_url(url: &str) -> Result<Url, String> {
+    let url = url.trim();
+    let parsed = Url::parse(url).map_err(|e| format!("Invalid API URL {url}: {e}"))?;
+    match parsed.scheme() {
+        "http" | "https" => Ok(parsed),
+        _ => Err(format!("Invalid API URL {url}: expected an http/https URL")),
+    }
+}
+
+const PROXY_SCHEMES: [&str; 6] = ["http", "https", "socks4", "socks4a", "socks5", "socks5h"];
+
+/// Parses a proxy URL such as `http://proxy:3128` or `socks5h://localhost:9050`,
+/// keeping the hosts excluded by `NO_PROXY`. An empty URL means no proxy.
+pub fn parse_proxy(url: &str) -> Result<Option<Proxy>, String> {
+    let url = url.trim();
+    if url.is_empty() {
+        return Ok(None);
+    }
+    let scheme = url.split_once("://").map(|(scheme, _)| scheme);
+    if !scheme.is_some_and(|scheme| PROXY_SCHEMES.contains(&scheme)) {
+        return Err(format!(
+            "Invalid proxy {url}: expected a {} URL",
+            PROXY_SCHEMES.join("/")
+        ));
+    }
+    let proxy = Proxy::all(url).map_err(|e| format!("Invalid proxy {url}: {e}"))?;
+    Ok(Some(proxy.no_proxy(NoProxy::from_env())))
+}
+
+/// A client for the API through `proxy`, see [`parse_proxy`].
+pub fn http_client(proxy: &str) -> Result<reqwest::Client, String> {
+    let builder = match parse_proxy(proxy)? {
+        Some(proxy) => reqwest::Client::builder().proxy(proxy),
+        None => reqwest::Client::builder(),
+    };
+    builder
+        .build()
+        .map_err(|e| format!("Could not create the HTTP client: {e}"))
 }
 
 impl Handler {
+    /// Starts a handler on a blocking Tokio thread, which stops when the
+    /// event sender is dropped.
+    pub fn spawn() -> (Sender<Event>, Receiver<EventResponse>) {
+        let (tx_event, rx_event) = channel::<Event>();
+        let (tx_response, rx_response) = channel::<EventResponse>();
+        let handler = Handler::new(rx_event, tx_response);
+        spawn_blocking(move || handler.listen());
+        (tx_event, rx_response)
+    }
     pub fn new(event_stream: Receiver<Event>, response_stream: Sender<EventResponse>) -> Self {
         Self {
             event_stream,
             response_stream,
+            http: RefCell::new(reqwest::Client::new()),
+            api: RefCell::new(Url::parse(API_URL).expect("the default API URL is valid")),
         }
     }
     pub fn listen(&self) {
         while let Ok(event) = self.event_stream.recv() {
             match event {
                 Event::DeleteAllEmails(email) => self.delete_all(email),
-                Event::DeleteEmail(id, index) => self.delete(id, index),
+                Event::DeleteEmail(id) => self.delete(id),
                 Event::FetchEmails(email) => self.fetch_emails(email, 0),
                 Event::FetchEmail(id) => self.fetch_email(id),
                 Event::FetchDomanins => self.fetch_domains(),
                 Event::CountEmails(email) => self.fetch_count(email),
                 Event::FetchMoreEmails(email, offset) => self.fetch_emails(email, offset),
                 Event::GetAttachments(id) => self.get_attachments(id),
+                Event::DownloadAttachment(id, path, limit) => {
+                    self.download_attachment(id, path, limit)
+                }
+                Event::SetProxy(proxy) => self.set_proxy(proxy),
+                Event::SetApiUrl(url) => self.set_api_url(url),
             }
         }
     }
 
-    fn delete_all(&self, email: String) {
-        let client = Client::new(email).unwrap();
-        let count = Handle::current().block_on(client.delete_all_emails());
-        match count {
-            Ok(_) => {
-                let _ = self.response_stream.send(EventResponse::EmailsDeleted);
+    fn respond(&self, response: EventResponse) {
+        let _ = self.response_stream.send(response);
+    }
+    fn fail(&self, message: String) {
+        error!("{message}");
+        self.respond(EventResponse::Error(message));
+    }
+    /// Fails the event if the address is invalid.
+    fn check_address(&self, email: &str) -> bool {
+        let valid = EmailAddress::is_valid(email);
+        if !valid {
+            self.fail(format!("Invalid email address: {email}"));
+        }
+        valid
+    }
+
+    /// The API URL with these path segments appended.
+    fn url(&self, segments: &[&str]) -> Url {
+        let mut url = self.api.borrow().clone();
+        if let Ok(mut path) = url.path_segments_mut() {
+            path.pop_if_empty().extend(segments);
+        }
+        url
+    }
+
+    /// Sends a request to the API and returns the result of the answer,
+    /// `None` for answers without one.
+    fn call<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        segments: &[&str],
+        query: &[(&str, u32)],
+    ) -> Result<Option<T>, String> {
+        let request = self
+            .http
+            .borrow()
+            .request(method, self.url(segments))
+            .query(query);
+        Handle::current().block_on(async {
+            let response = request.send().await.map_err(|e| e.to_string())?;
+            let status = response.status();
+            let answer =
+                response
+                    .json::<Answer<T>>()
+                    .await
+                    .map_err(|e| match status.is_success() {
+                        true => e.to_string(),
+                        false => format!("The server answered {status}"),
+                    })?;
+            match answer.success {
+                true => Ok(answer.result),
+                false => Err(answer
+                    .error
+                    .map(|error| error.message)
+                    .unwrap_or_else(|| format!("The server answered {status}"))),
+            }
+        })
+    }
+
+    /// Like [`Self::call`] for answers that must have a result.
+    fn get<T: DeserializeOwned>(
+        &self,
+        segments: &[&str],
+        query: &[(&str, u32)],
+    ) -> Result<T, String> {
+        self.call(Method::GET, segments, query)?
+            .ok_or_else(|| "The server sent no result".to_owned())
+    }
+
+    fn set_proxy(&self, proxy: String) {
+        match http_client(&proxy) {
+            Ok(http) => {
+                *self.http.borrow_mut() = http;
+                self.respond(EventResponse::ProxySet);
             }
-            Err(e) => error!("Could not delete all emails{e:?}"),
+            Err(e) => self.fail(e),
         }
     }
-    fn delete(&self, id: String, index: usize) {
-        let client = Client::new("example@example.com").unwrap();
-        let status = Handle::current().block_on(client.delete_inbox(id));
-        match status {
-            Ok(()) => {
-                let _ = self
-                    .response_stream
-                    .send(EventResponse::EmailDeleted(index));
+
+    fn set_api_url(&self, url: String) {
+        match parse_api_url(&url) {
+            Ok(url) => {
+                *self.api.borrow_mut() = url;
+                self.respond(EventResponse::ApiUrlSet);
             }
-            Err(e) => error!("Could not delete email: {e:?}"),
+            Err(e) => self.fail(e),
+        }
+    }
+
+    fn delete_all(&self, email: String) {
+        if !self.check_address(&email) {
+            return;
+        }
+        match self.call::<IgnoredAny>(Method::DELETE, &["emails", &email], &[]) {
+            Ok(_) => self.respond(EventResponse::EmailsDeleted),
+            Err(e) => self.fail(format!("Could not delete all emails: {e}")),
+        }
+    }
+    fn delete(&self, id: String) {
+        match self.call::<IgnoredAny>(Method::DELETE, &["inbox", &id], &[]) {
+            Ok(_) => self.respond(EventResponse::EmailDeleted(id)),
+            Err(e) => self.fail(format!("Could not delete email: {e}")),
         }
     }
 
     fn fetch_emails(&self, email: String, offset: u32) {
-        let client = Client::new(email).unwrap();
-        let emails = Handle::current().block_on(client.get_emails(50, 0));
+        if !self.check_address(&email) {
+            return;
+        }
+        let emails = self.get(&["emails", &email], &[("limit", 50), ("offset", 0)]);
         match emails {
             Ok(emails) => {
                 if offset == 0 {
-                    let _ = self.response_stream.send(EventResponse::Emails(emails));
+                    self.respond(EventResponse::Emails(email, emails));
                 } else {
-                    let _ = self.response_stream.send(EventResponse::EmailsMore(emails));
+                    self.respond(EventResponse::EmailsMore(emails));
                 }
             }
-            Err(e) => error!("Could not fetch emails: {e:?}"),
+            Err(e) => self.fail(format!("Could not fetch emails: {e}")),
         }
     }
     fn fetch_email(&self, id: String) {
-        let client = Client::new("example@example.com").unwrap();
-        let email = Handle::current().block_on(client.get_inbox(id));
-        match email {
-            Ok(email) => {
-                let _ = self.response_stream.send(EventResponse::Email(email));
-            }
-            Err(e) => error!("Could not fetch email: {e:?}"),
+        match self.get(&["inbox", &id], &[]) {
+            Ok(email) => self.respond(EventResponse::Email(email)),
+            Err(e) => self.fail(format!("Could not fetch email: {e}")),
         }
     }
 
     fn fetch_count(&self, email: String) {
-        let client = Client::new(email).unwrap();
-        let count = Handle::current().block_on(client.email_count());
-        match count {
-            Ok(count) => {
-                let _ = self.response_stream.send(EventResponse::Count(count));
-            }
-            Err(e) => error!("Could not fetch count: {e:?}"),
+        if !self.check_address(&email) {
+            return;
+        }
+        match self.get::<Count>(&["emails", "count", &email], &[]) {
+            Ok(Count { count }) => self.respond(EventResponse::Count(count)),
+            Err(e) => self.fail(format!("Could not fetch count: {e}")),
         }
     }
 
     fn fetch_domains(&self) {
-        let client = Client::new("example@example.com").unwrap();
-        let domains = Handle::current().block_on(client.get_domains());
-        match domains {
-            Ok(domains) => {
-                let _ = self.response_stream.send(EventResponse::Domains(domains));
-            }
-            Err(e) => error!("Could not fetch domains: {e:?}"),
+        match self.get(&["domains"], &[]) {
+            Ok(domains) => self.respond(EventResponse::Domains(domains)),
+            Err(e) => self.fail(format!("Could not fetch domains: {e}")),
         }
     }
 
     fn get_attachments(&self, id: String) {
-        let client = Client::new("example@example.com").unwrap();
-        let attachments = Handle::current().block_on(client.get_attachments(id));
-        match attachments {
-            Ok(attachments) => {
-                let _ = self
-                    .response_stream
-                    .send(EventResponse::Attachments(attachments));
+        match self.get(&["inbox", &id, "attachments"], &[]) {
+            Ok(attachments) => self.respond(EventResponse::Attachments(attachments)),
+            Err(e) => self.fail(format!("Could not fetch attachments: {e}")),
+        }
+    }
+
+    fn download_attachment(&self, id: String, path: PathBuf, limit: u64) {
+        let saved = Handle::current().block_on(self.stream_attachment(&id, &path, limit));
+        match saved {
+            Ok(()) => self.respond(EventResponse::AttachmentSaved(path)),
+            Err(e) => {
+                let _ = std::fs::remove_file(&path);
+                self.fail(format!("Could not download attachment: {e}"));
+            }
+        }
+    }
+
+    /// Writes the attachment chunk by chunk, so the size is checked before
+    /// all of it is in memory.
+    async fn stream_attachment(&self, id: &str, path: &Path, limit: u64) -> Result<(), String> {
+        let request = self.http.borrow().get(self.url(&["attachments", id]));
+        let mut response = request.send().await.map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("The server answered {}", response.status()));
+        }
+        let too_large = || format!("Larger than the limit of {}", UnitSize(limit));
+        if limit > 0
+            && response
+                .content_length()
+                .is_some_and(|length| length > limit)
+        {
+            return Err(too_large());
+        }
+        let mut file = File::create(path).map_err(|e| e.to_string())?;
+        let mut size = 0;
+        while let Some(chunk) = response.chunk().await.map_err(|e| e.to_string())? {
+            size += chunk.len() as u64;
+            if limit > 0 && size > limit {
+                return Err(too_large());
             }
-            Err(e) => error!("Could not fetch attachments: {e:?}"),
+            file.write_all(&chunk).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+}
+
+/// Blocking request/response access to a [`Handler`], for scripts and tools.
+pub struct Backend {
+    events: Sender<Event>,
+    responses: Receiver<EventResponse>,
+    /// The proxy last set, empty for the environment's.
+    proxy: String,
+    /// The API URL last set.
+    api_url: String,
+}
+
+impl Backend {
+    pub fn spawn() -> Self {
+        let (events, responses) = Handler::spawn();
+        Self {
+            events,
+            responses,
+            proxy: String::new(),
+            api_url: API_URL.into(),
+        }
+    }
+
+    /// Switches to `proxy` unless it is already in use, see
+    /// [`Event::SetProxy`].
+    pub fn use_proxy(&mut self, proxy: &str) -> Result<(), String> {
+        if proxy != self.proxy {
+            self.request(Event::SetProxy(proxy.to_owned()))?;
+            self.proxy = proxy.to_owned();
+        }
+        Ok(())
+    }
+
+    /// Switches to the API at `url` unless it is already in use, see
+    /// [`Event::SetApiUrl`].
+    pub fn use_api_url(&mut self, url: &str) -> Result<(), String> {
+        if url != self.api_url {
+            self.request(Event::SetApiUrl(url.to_owned()))?;
+            self.api_url = url.to_owned();
         }
+        Ok(())
+    }
+
+    /// Sends an event and waits for its response, turning
+    /// [`EventResponse::Error`] into `Err`.
+    pub fn request(&self, event: Event) -> Result<EventResponse, String> {
+        self.events
+            .send(event)
+            .map_err(|_| "The backend stopped".to_owned())?;
+        match self.responses.recv() {
+            Ok(EventResponse::Error(message)) => Err(message),
+            Ok(response) => Ok(response),
+            Err(_) => Err("The backend stopped".into()),
+        }
+    }
+
+    /// Fetches the body of an email from the inbox listing if it is missing.
+    pub fn request_full_email(&self, email: Email) -> Result<Email, String> {
+        if email.text_content.is_some() || email.html_content.is_some() {
+            return Ok(email);
+        }
+        match self.request(Event::FetchEmail(email.id))? {
+            EventResponse::Email(email) => Ok(email),
+            _ => Err("Unexpected response from the backend".into()),
+        }
+    }
+}
+
+/// Work for a [`Worker`].
+pub trait Job: Send + 'static {
+    type Report: Send + 'static;
+
+    /// See [`Event::SetProxy`].
+    fn proxy(&self) -> &str;
+
+    /// See [`Event::SetApiUrl`].
+    fn api_url(&self) -> &str;
+
+    fn run(self, backend: &Backend) -> Self::Report;
+
+    /// The report of a job that did not run, as the backend could not be
+    /// switched to its proxy or API.
+    fn failed(self, error: String) -> Self::Report;
+}
+
+/// Runs [`Job`]s one after another on a thread with its own [`Backend`],
+/// switched to the proxy and API each job asks for, or skipped with
+/// [`Job::failed`] if that fails. The thread is inside the Tokio runtime, so
+/// jobs can block on futures.
+pub struct Worker<J: Job> {
+    jobs: Sender<J>,
+    reports: Receiver<J::Report>,
+}
+
+impl<J: Job> Worker<J> {
+    /// Needs a Tokio runtime, like [`Backend::spawn`].
+    pub fn spawn() -> Self {
+        let mut backend = Backend::spawn();
+        let runtime = Handle::current();
+        let (jobs, job_stream) = channel::<J>();
+        let (report_stream, reports) = channel();
+        std::thread::spawn(move || {
+            let _runtime = runtime.enter();
+            while let Ok(job) = job_stream.recv() {
+                // A job must not go out through another proxy or API than
+                // the one it asked for.
+                let switched = backend
+                    .use_proxy(job.proxy())
+                    .and_then(|()| backend.use_api_url(job.api_url()));
+                let report = match switched {
+                    Ok(()) => job.run(&backend),
+                    Err(e) => {
+                        error!("Skipping a job: {e}");
+                        job.failed(e)
+                    }
+                };
+                if report_stream.send(report).is_err() {
+                    break;
+                }
+            }
+        });
+        Self { jobs, reports }
+    }
+
+    pub fn run(&self, job: J) {
+        let _ = self.jobs.send(job);
+    }
+
+    /// The report of a finished job.
+    pub fn try_report(&self) -> Option<J::Report> {
+        self.reports.try_recv().ok()
     }
 }
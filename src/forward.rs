@@ -0,0 +1,308 @@
+//! Forwarding emails to a real mailbox over SMTP.
+//!
+//! An [`Email`] is rebuilt as a MIME message with its text and HTML bodies
+//! and attachments, sent from the configured account to the destination with
+//! the original sender as `Reply-To`.
+
+use std::time::{Duration, UNIX_EPOCH};
+
+use lettre::{
+    Message, SmtpTransport, Transport,
+    message::{Attachment as MimeAttachment, Mailbox, MultiPart, SinglePart, header::ContentType},
+    transport::smtp::authentication::Credentials,
+};
+use serde::{Deserialize, Serialize};
+use tmapi::{Attachment, Email};
+
+use crate::{
+    attachments::{declared_risk, sniff},
+    event_handler::{Backend, Event, EventResponse, Job, Worker},
+    i18n::tr,
+    inbox::safe_filename,
+};
+
+const SMTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Attachments with their contents.
+pub type Downloaded = Vec<(Attachment, Vec<u8>)>;
+
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum Security {
+    #[default]
+    StartTls,
+    Tls,
+    /// Unencrypted, for local test servers.
+    None,
+}
+
+impl Security {
+    pub const ALL: [Self; 3] = [Self::StartTls, Self::Tls, Self::None];
+
+    pub fn name(&self) -> String {
+        match self {
+            Self::StartTls => "STARTTLS".into(),
+            Self::Tls => "TLS".into(),
+            Self::None => tr("forward_security_none"),
+        }
+    }
+
+    pub fn default_port(&self) -> u16 {
+        match self {
+            Self::StartTls => 587,
+            Self::Tls => 465,
+            Self::None => 25,
+        }
+    }
+}
+
+/// Where to forward the emails of an address.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Forwarding {
+    pub host: String,
+    pub port: u16,
+    pub security: Security,
+    /// No authentication when empty.
+    pub username: String,
+    pub password: String,
+    /// The sender, the username when empty.
+    pub from: String,
+    pub to: String,
+    /// Forwards new emails without asking.
+    pub automatic: bool,
+}
+
+impl Default for Forwarding {
+    fn default() -> Self {
+        Self {
+            host: String::new(),
+            port: Security::StartTls.default_port(),
+            security: Security::StartTls,
+            username: String::new(),
+            password: String::new(),
+            from: String::new(),
+            to: String::new(),
+            automatic: false,
+        }
+    }
+}
+
+impl Forwarding {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Describes the first setting that keeps emails from being sent.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.host.trim().is_empty() {
+            return Err("The SMTP server is missing".into());
+        }
+        self.sender()?;
+        self.to
+            .parse::<Mailbox>()
+            .map_err(|e| format!("Invalid destination {}: {e}", self.to))?;
+        // Without encryption the login would cross the network readable.
+        if self.security == Security::None && !self.username.is_empty() {
+            return Err("Refusing to log in without encryption, choose STARTTLS or TLS".into());
+        }
+        Ok(())
+    }
+
+    fn sender(&self) -> Result<Mailbox, String> {
+        let from = match self.from.trim() {
+            "" => self.username.trim(),
+            from => from,
+        };
+        from.parse()
+            .map_err(|e| format!("Invalid sender {from:?}: {e}"))
+    }
+
+    fn transport(&self) -> Result<SmtpTransport, String> {
+        let host = self.host.trim();
+        let builder = match self.security {
+            Security::StartTls => SmtpTransport::starttls_relay(host),
+            Security::Tls => SmtpTransport::relay(host),
+            Security::None => Ok(SmtpTransport::builder_dangerous(host)),
+        }
+        .map_err(|e| format!("Invalid SMTP server {host}: {e}"))?;
+        let builder = builder.port(self.port).timeout(Some(SMTP_TIMEOUT));
+        let builder = match self.username.is_empty() {
+            true => builder,
+            false => builder.credentials(Credentials::new(
+                self.username.clone(),
+                self.password.clone(),
+            )),
+        };
+        Ok(builder.build())
+    }
+
+    /// Rebuilds the email as a MIME message to the destination.
+    pub fn message(&self, email: &Email, attachments: Downloaded) -> Result<Message, String> {
+        let to = self
+            .to
+            .parse::<Mailbox>()
+            .map_err(|e| format!("Invalid destination {}: {e}", self.to))?;
+        let mut builder = Message::builder()
+            .from(self.sender()?)
+            .to(to)
+            .subject(&email.subject)
+            .date(UNIX_EPOCH + Duration::from_secs(email.received_at.max(0) as u64));
+        if let Ok(sender) = email.from_address.parse::<Mailbox>() {
+            builder = builder.reply_to(sender);
+        }
+        let text = email.text_content.clone().unwrap_or_default();
+        let alternative = (email.html_content.clone())
+            .map(|html| MultiPart::alternative_plain_html(text.clone(), html));
+        let message = if attachments.is_empty() {
+            match alternative {
+                Some(body) => builder.multipart(body),
+                None => builder.singlepart(SinglePart::plain(text)),
+            }
+        } else {
+            let mixed = match alternative {
+                Some(body) => MultiPart::mixed().multipart(body),
+                None => MultiPart::mixed().singlepart(SinglePart::plain(text)),
+            };
+            let mixed = attachments
+                .into_iter()
+                .fold(mixed, |mixed, (attachment, bytes)| {
+                    let content_type = ContentType::parse(&attachment.content_type)
+                        .or_else(|_| ContentType::parse("application/octet-stream"))
+                        .expect("a valid content type");
+                    let part = MimeAttachment::new(safe_filename(&attachment));
+                    mixed.singlepart(part.body(bytes, content_type))
+                });
+            builder.multipart(mixed)
+        };
+        message.map_err(|e| format!("Could not build the message: {e}"))
+    }
+}
+
+/// Emails to forward.
+pub struct ForwardJob {
+    pub forwarding: Forwarding,
+    pub emails: Vec<Email>,
+    /// See [`Event::SetProxy`], used to fetch the emails. SMTP connects
+    /// directly.
+    pub proxy: String,
+    /// See [`Event::SetApiUrl`].
+    pub api_url: String,
+    /// Larger attachments are left out, 0 for no limit.
+    pub max_download: u64,
+}
+
+/// The result of a [`ForwardJob`].
+pub struct ForwardReport {
+    /// Subjects of the forwarded emails.
+    pub forwarded: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+/// Sends [`ForwardJob`]s off the UI thread, its [`Backend`] fetches the
+/// bodies and attachments.
+pub type ForwardRunner = Worker<ForwardJob>;
+
+impl Job for ForwardJob {
+    type Report = ForwardReport;
+
+    fn proxy(&self) -> &str {
+        &self.proxy
+    }
+
+    fn api_url(&self) -> &str {
+        &self.api_url
+    }
+
+    fn run(self, backend: &Backend) -> ForwardReport {
+        run(backend, self)
+    }
+
+    fn failed(self, error: String) -> ForwardReport {
+        ForwardReport {
+            forwarded: Vec::new(),
+            errors: vec![error],
+        }
+    }
+}
+
+fn run(backend: &Backend, job: ForwardJob) -> ForwardReport {
+    let mut report = ForwardReport {
+        forwarded: Vec::new(),
+        errors: Vec::new(),
+    };
+    let transport = match job
+        .forwarding
+        .validate()
+        .and_then(|()| job.forwarding.transport())
+    {
+        Ok(transport) => transport,
+        Err(e) => {
+            report.errors.push(e);
+            return report;
+        }
+    };
+    for email in job.emails {
+        let subject = email.subject.clone();
+        let sent = fetch(backend, email, job.max_download, &mut report.errors)
+            .and_then(|(email, attachments)| job.forwarding.message(&email, attachments))
+            .and_then(|message| {
+                transport
+                    .send(&message)
+                    .map_err(|e| format!("Could not forward \"{subject}\": {e}"))
+            });
+        match sent {
+            Ok(_) => report.forwarded.push(subject),
+            Err(e) => report.errors.push(e),
+        }
+    }
+    report
+}
+
+/// The full email with the contents of its attachments. Attachments that are
+/// too large or look dangerous are left out, with a note in `skipped`.
+fn fetch(
+    backend: &Backend,
+    email: Email,
+    max_download: u64,
+    skipped: &mut Vec<String>,
+) -> Result<(Email, Downloaded), String> {
+    let email = backend.request_full_email(email)?;
+    if !email.has_attachments {
+        return Ok((email, Vec::new()));
+    }
+    let list = match backend.request(Event::GetAttachments(email.id.clone()))? {
+        EventResponse::Attachments(list) => list,
+        _ => return Err("Unexpected response from the backend".into()),
+    };
+    let dir = tempfile::tempdir().map_err(|e| format!("Could not create a directory: {e}"))?;
+    let mut attachments = Vec::new();
+    for (index, attachment) in list.into_iter().enumerate() {
+        let name = &attachment.filename;
+        let subject = &email.subject;
+        if max_download > 0 && attachment.size > max_download {
+            skipped.push(format!(
+                "Left out {name} of \"{subject}\", it is larger than the download limit"
+            ));
+            continue;
+        }
+        if let Some(risk) = declared_risk(&attachment) {
+            let risk = risk.describe();
+            skipped.push(format!("Left out {name} of \"{subject}\": {risk}"));
+            continue;
+        }
+        let path = dir.path().join(index.to_string());
+        backend.request(Event::DownloadAttachment(
+            attachment.id.clone(),
+            path.clone(),
+            max_download,
+        ))?;
+        let bytes = std::fs::read(&path).map_err(|e| format!("Could not read {name}: {e}"))?;
+        if let Some(risk) = sniff(&bytes) {
+            let risk = risk.describe();
+            skipped.push(format!("Left out {name} of \"{subject}\": {risk}"));
+            continue;
+        }
+        attachments.push((attachment, bytes));
+    }
+    Ok((email, attachments))
+}
@@ -0,0 +1,343 @@
+use std::{collections::HashSet, path::Path, thread, time::Instant};
+
+use rand::seq::IndexedRandom;
+use serde_json::json;
+use tmapi::Email;
+use tmgui::{
+    attachments::{check_file, declared_risk},
+    dates::iso_date,
+    event_handler::{Backend, Event, EventResponse},
+    inbox::{self, EmailFilter, email_details_json, email_json, safe_filename},
+    state::random_name,
+};
+
+use crate::{
+    cli::{Args, Command, Extract, WaitFor},
+    config::Settings,
+};
+
+const TIMEOUT_EXIT: i32 = 2;
+
+pub fn run(command: Command, args: &Args) -> i32 {
+    let (config, errors) = args.config();
+    for error in errors {
+        eprintln!("warning: {error}");
+    }
+    let mut settings = match args.stored_state() {
+        Ok(state) => state.settings,
+        Err(message) => {
+            eprintln!("error: {message}");
+            return 1;
+        }
+    };
+    settings.overlay = config;
+    let backend = Backend::spawn();
+    let connected = backend
+        .request(Event::SetProxy(settings.proxy().into()))
+        .and_then(|_| backend.request(Event::SetApiUrl(settings.api_url().into())));
+    if let Err(message) = connected {
+        eprintln!("error: {message}");
+        return 1;
+    }
+    let result = match command {
+        Command::WaitFor(options) => wait_for(&backend, args, options),
+        command => execute(&backend, command, args, &settings).map(|()| 0),
+    };
+    match result {
+        Ok(code) => code,
+        Err(message) => {
+            eprintln!("error: {message}");
+            1
+        }
+    }
+}
+
+pub fn address(args: &Args) -> Result<String, String> {
+    let (name, domain) = args.name_and_domain();
+    let stored = args.stored_state()?;
+    match (name.or(stored.name), domain.or(stored.domain)) {
+        (Some(name), Some(domain)) => Ok([name, domain].join("@")),
+        _ if stored.vault.is_some() => {
+            Err("The stored address is encrypted, use --address or --name and --domain".into())
+        }
+        _ => Err("No address given, use --address or --name and --domain".into()),
+    }
+}
+
+pub fn print_json(value: &serde_json::Value) {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(value).unwrap_or_default()
+    );
+}
+
+fn execute(
+    backend: &Backend,
+    command: Command,
+    args: &Args,
+    settings: &Settings,
+) -> Result<(), String> {
+    match command {
+        Command::List => list(backend, args),
+        Command::Read { id } => read(backend, args, id),
+        Command::Delete { id } => delete(backend, args, id),
+        Command::Domains => domains(backend, args),
+        Command::NewAddress => new_address(backend, args),
+        Command::ExportSettings { path } => args.stored_state()?.export(&path),
+        Command::ImportSettings { path, sensitive } => {
+            let mut state = args.stored_state()?;
+            let changes = state.import(&path, sensitive)?;
+            if !changes.is_empty() && !sensitive {
+                eprintln!(
+                    "warning: skipped {}, pass --sensitive to import them too",
+                    changes.join(", ")
+                );
+            }
+            args.store_state(&state)
+                .map_err(|e| format!("Could not save the settings: {e}"))
+        }
+        Command::Attachments {
+            id,
+            save,
+            allow_large,
+        } => {
+            let limit = match allow_large {
+                true => 0,
+                false => settings.max_download_bytes(),
+            };
+            attachments(backend, args, id, save.as_deref(), limit)
+        }
+        Command::WaitFor(_) => unreachable!("wait-for is run separately"),
+    }
+}
+
+fn list(backend: &Backend, args: &Args) -> Result<(), String> {
+    let EventResponse::Emails(_, emails) = backend.request(Event::FetchEmails(address(args)?))?
+    else {
+        return Err(unexpected());
+    };
+    if args.json {
+        print_json(&json!(emails.iter().map(email_json).collect::<Vec<_>>()));
+        return Ok(());
+    }
+    for email in emails {
+        println!(
+            "{}\t{}\t{}\t{}",
+            email.id,
+            iso_date(email.received_at),
+            email.from_address,
+            email.subject
+        );
+    }
+    Ok(())
+}
+
+fn read(backend: &Backend, args: &Args, id: String) -> Result<(), String> {
+    let EventResponse::Email(email) = backend.request(Event::FetchEmail(id))? else {
+        return Err(unexpected());
+    };
+    if args.json {
+        print_json(&email_json(&email));
+        return Ok(());
+    }
+    println!("From:    {}", email.from_address);
+    println!("To:      {}", email.to_address);
+    println!("Date:    {}", iso_date(email.received_at));
+    println!("Subject: {}", email.subject);
+    if email.has_attachments {
+        println!("Attachments: {}", email.attachment_count);
+    }
+    println!();
+    println!("{}", email.text_content.unwrap_or_default());
+    Ok(())
+}
+
+fn delete(backend: &Backend, args: &Args, id: String) -> Result<(), String> {
+    let EventResponse::EmailDeleted(_) = backend.request(Event::DeleteEmail(id.clone()))? else {
+        return Err(unexpected());
+    };
+    match args.json {
+        true => print_json(&json!({ "deleted": id })),
+        false => println!("Deleted {id}"),
+    }
+    Ok(())
+}
+
+fn domains(backend: &Backend, args: &Args) -> Result<(), String> {
+    let domains = fetch_domains(backend)?;
+    match args.json {
+        true => print_json(&json!(domains)),
+        false => domains.iter().for_each(|domain| println!("{domain}")),
+    }
+    Ok(())
+}
+
+fn new_address(backend: &Backend, args: &Args) -> Result<(), String> {
+    let domain = match args.name_and_domain().1.or(args.config().0.default_domain) {
+        Some(domain) => domain,
+        None => fetch_domains(backend)?
+            .choose(&mut rand::rng())
+            .cloned()
+            .ok_or("No domains are available")?,
+    };
+    let address = [random_name(10), domain].join("@");
+    match args.json {
+        true => print_json(&json!({ "address": address })),
+        false => println!("{address}"),
+    }
+    Ok(())
+}
+
+fn attachments(
+    backend: &Backend,
+    args: &Args,
+    id: String,
+    save: Option<&Path>,
+    limit: u64,
+) -> Result<(), String> {
+    let EventResponse::Attachments(attachments) = backend.request(Event::GetAttachments(id))?
+    else {
+        return Err(unexpected());
+    };
+    let mut saved = Vec::new();
+    if let Some(dir) = save {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Could not create {dir:?}: {e}"))?;
+        for attachment in &attachments {
+            let name = &attachment.filename;
+            if limit > 0 && attachment.size > limit {
+                eprintln!(
+                    "warning: skipped {name}, it is larger than the download limit, pass --allow-large to save it"
+                );
+                saved.push(None);
+                continue;
+            }
+            let path = dir.join(safe_filename(attachment));
+            let EventResponse::AttachmentSaved(path) = backend.request(
+                Event::DownloadAttachment(attachment.id.clone(), path, limit),
+            )?
+            else {
+                return Err(unexpected());
+            };
+            let check = check_file(&path).map_err(|e| format!("Could not check {name}: {e}"))?;
+            if let Some(risk) = declared_risk(attachment).or(check.risk) {
+                eprintln!("warning: {name}: {}", risk.describe());
+            }
+            saved.push(Some((path, check)));
+        }
+    }
+
+    if args.json {
+        let attachments = attachments
+            .iter()
+            .enumerate()
+            .map(|(index, attachment)| {
+                let saved = saved.get(index).and_then(Option::as_ref);
+                json!({
+                    "id": attachment.id,
+                    "filename": attachment.filename,
+                    "content_type": attachment.content_type,
+                    "size": attachment.size,
+                    "created_at": attachment.created_at,
+                    "saved_to": saved.map(|(path, _)| path),
+                    "sha256": saved.map(|(_, check)| &check.sha256),
+                })
+            })
+            .collect::<Vec<_>>();
+        print_json(&json!(attachments));
+        return Ok(());
+    }
+    for (index, attachment) in attachments.iter().enumerate() {
+        print!(
+            "{}\t{}\t{}\t{}",
+            attachment.id, attachment.size, attachment.content_type, attachment.filename
+        );
+        match saved.get(index).and_then(Option::as_ref) {
+            Some((path, _)) => println!("\t{}", path.display()),
+            None => println!(),
+        }
+    }
+    Ok(())
+}
+
+fn wait_for(backend: &Backend, args: &Args, options: WaitFor) -> Result<i32, String> {
+    let address = address(args)?;
+    let filter = EmailFilter {
+        from: options.from,
+        subject: options.subject,
+        body: options.body,
+    };
+    let deadline = Instant::now() + options.timeout;
+    let mut seen = HashSet::new();
+    // Matching emails without a code or link, which are not checked again.
+    let mut without_extract = Vec::new();
+    let mut first = true;
+    loop {
+        match backend.request(Event::FetchEmails(address.clone())) {
+            Ok(EventResponse::Emails(_, emails)) => {
+                for email in emails {
+                    let new = seen.insert(email.id.clone());
+                    if !new || (first && options.new_only) || !filter.matches_headers(&email) {
+                        continue;
+                    }
+                    let id = email.id.clone();
+                    let email = match backend.request_full_email(email) {
+                        Ok(email) => email,
+                        Err(message) => {
+                            eprintln!("warning: {message}");
+                            seen.remove(&id);
+                            continue;
+                        }
+                    };
+                    if !filter.matches(&email) {
+                        continue;
+                    }
+                    match wait_for_output(&email, options.extract) {
+                        Some(output) => {
+                            print_json(&output);
+                            return Ok(0);
+                        }
+                        None => without_extract.push(format!("{} ({})", email.id, email.subject)),
+                    }
+                }
+                first = false;
+            }
+            Ok(_) => return Err(unexpected()),
+            Err(message) => eprintln!("warning: {message}"),
+        }
+
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            eprintln!(
+                "error: no matching email arrived within {}",
+                humantime::format_duration(options.timeout)
+            );
+            if !without_extract.is_empty() {
+                eprintln!(
+                    "note: these emails matched but had no code or link: {}",
+                    without_extract.join(", ")
+                );
+            }
+            return Ok(TIMEOUT_EXIT);
+        }
+        thread::sleep(options.interval.min(remaining));
+    }
+}
+
+fn wait_for_output(email: &Email, extract: Option<Extract>) -> Option<serde_json::Value> {
+    match extract {
+        Some(Extract::Code) => inbox::verification_code(email).map(|code| json!(code)),
+        Some(Extract::Link) => inbox::verification_link(email).map(|link| json!(link)),
+        None => Some(email_details_json(email)),
+    }
+}
+
+fn fetch_domains(backend: &Backend) -> Result<Vec<String>, String> {
+    match backend.request(Event::FetchDomanins)? {
+        EventResponse::Domains(domains) => Ok(domains),
+        _ => Err(unexpected()),
+    }
+}
+
+fn unexpected() -> String {
+    "Unexpected response from the backend".into()
+}
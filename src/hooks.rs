@@ -0,0 +1,230 @@
+//! A command and a webhook run for each new email, with retries.
+
+use std::{
+    io::{Read, Write},
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use log::{error, info, warn};
+use tmapi::Email;
+use tokio::runtime::Handle;
+
+use crate::{
+    event_handler::{Backend, Job, Worker, http_client},
+    inbox::{self, email_details_json},
+};
+
+const ATTEMPTS: u32 = 3;
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+/// A command still running after this is killed.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// New emails of `address` to hand to the hooks.
+pub struct HookJob {
+    pub address: String,
+    pub emails: Vec<Email>,
+    /// Run with `sh -c`, or `cmd /C` on Windows. Empty for none.
+    pub command: String,
+    /// POSTed to. Empty for none.
+    pub url: String,
+    /// See [`Event::SetProxy`].
+    pub proxy: String,
+    /// See [`Event::SetApiUrl`].
+    pub api_url: String,
+}
+
+/// One hook run for one email.
+pub struct HookLog {
+    pub at: i64,
+    pub subject: String,
+    pub target: String,
+    pub attempts: u32,
+    pub result: Result<(), String>,
+}
+
+/// Runs [`HookJob`]s off the UI thread, its [`Backend`] fetches the bodies.
+/// Reports the logs of each job.
+pub type HookRunner = Worker<HookJob>;
+
+impl Job for HookJob {
+    type Report = Vec<HookLog>;
+
+    fn proxy(&self) -> &str {
+        &self.proxy
+    }
+
+    fn api_url(&self) -> &str {
+        &self.api_url
+    }
+
+    fn run(self, backend: &Backend) -> Vec<HookLog> {
+        run(backend, &Handle::current(), self)
+    }
+
+    /// Logs each email as not handed to any hook.
+    fn failed(self, error: String) -> Vec<HookLog> {
+        let at = chrono::Utc::now().timestamp();
+        self.emails
+            .into_iter()
+            .map(|email| HookLog {
+                at,
+                subject: email.subject,
+                target: self.address.clone(),
+                attempts: 0,
+                result: Err(error.clone()),
+            })
+            .collect()
+    }
+}
+
+fn run(backend: &Backend, runtime: &Handle, job: HookJob) -> Vec<HookLog> {
+    let mut logs = Vec::new();
+    let http = match job.url.trim() {
+        "" => None,
+        _ => Some(http_client(&job.proxy)),
+    };
+    for email in job.emails {
+        let subject = email.subject.clone();
+        let email = match backend.request_full_email(email) {
+            Ok(email) => email,
+            Err(e) => {
+                logs.push(HookLog {
+                    at: chrono::Utc::now().timestamp(),
+                    subject,
+                    target: job.address.clone(),
+                    attempts: 1,
+                    result: Err(e),
+                });
+                continue;
+            }
+        };
+        let mut payload = email_details_json(&email);
+        payload["address"] = job.address.clone().into();
+        let body = serde_json::to_string(&payload).unwrap_or_default();
+        if !job.command.trim().is_empty() {
+            logs.push(retry(&subject, &job.command, || {
+                run_command(&job.command, &job.address, &email, &body)
+            }));
+        }
+        if let Some(http) = &http {
+            logs.push(retry(&subject, &job.url, || match http {
+                Ok(client) => post(runtime, client, &job.url, &body),
+                Err(e) => Err(e.clone()),
+            }));
+        }
+    }
+    logs
+}
+
+fn retry(subject: &str, target: &str, mut hook: impl FnMut() -> Result<(), String>) -> HookLog {
+    let mut attempts = 0;
+    let result = loop {
+        attempts += 1;
+        match hook() {
+            Ok(()) => break Ok(()),
+            Err(e) if attempts < ATTEMPTS => {
+                warn!("Hook {target} failed, retrying: {e}");
+                thread::sleep(RETRY_DELAY * attempts);
+            }
+            Err(e) => break Err(e),
+        }
+    };
+    match &result {
+        Ok(()) => info!("Hook {target} ran for \"{subject}\""),
+        Err(e) => warn!("Hook {target} failed for \"{subject}\": {e}"),
+    }
+    HookLog {
+        at: chrono::Utc::now().timestamp(),
+        subject: subject.to_owned(),
+        target: target.to_owned(),
+        attempts,
+        result,
+    }
+}
+
+/// Passes the email as JSON on stdin and the address, id, code and link in
+/// `TMGUI_*` variables.
+///
+/// Stdin is written and stderr read on their own threads, so a command that
+/// does neither in the expected order cannot block the hooks.
+fn run_command(command: &str, address: &str, email: &Email, body: &str) -> Result<(), String> {
+    let (shell, flag) = match cfg!(windows) {
+        true => ("cmd", "/C"),
+        false => ("sh", "-c"),
+    };
+    let mut child = Command::new(shell)
+        .args([flag, command])
+        .env("TMGUI_ADDRESS", address)
+        .env("TMGUI_EMAIL_ID", &email.id)
+        .env(
+            "TMGUI_CODE",
+            inbox::verification_code(email).unwrap_or_default(),
+        )
+        .env(
+            "TMGUI_LINK",
+            inbox::verification_link(email).unwrap_or_default(),
+        )
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Could not run the command: {e}"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        let body = body.to_owned();
+        // The command may not read its input.
+        thread::spawn(move || stdin.write_all(body.as_bytes()));
+    }
+    let stderr = child.stderr.take().map(|mut stderr| {
+        thread::spawn(move || {
+            let mut output = Vec::new();
+            let _ = stderr.read_to_end(&mut output);
+            output
+        })
+    });
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if started.elapsed() < COMMAND_TIMEOUT => thread::sleep(POLL_INTERVAL),
+            Ok(None) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                error!("Hook command {command} timed out, killed it");
+                return Err(format!(
+                    "Timed out after {} seconds",
+                    COMMAND_TIMEOUT.as_secs()
+                ));
+            }
+            Err(e) => return Err(format!("Could not run the command: {e}")),
+        }
+    };
+    if status.success() {
+        return Ok(());
+    }
+    let stderr = stderr
+        .and_then(|reader| reader.join().ok())
+        .unwrap_or_default();
+    Err(format!(
+        "{status}: {}",
+        String::from_utf8_lossy(&stderr).trim()
+    ))
+}
+
+fn post(runtime: &Handle, client: &reqwest::Client, url: &str, body: &str) -> Result<(), String> {
+    let request = client
+        .post(url)
+        .header("Content-Type", "application/json")
+        .timeout(WEBHOOK_TIMEOUT)
+        .body(body.to_owned());
+    let response = runtime
+        .block_on(async { request.send().await })
+        .map_err(|e| e.to_string())?;
+    match response.status() {
+        status if status.is_success() => Ok(()),
+        status => Err(format!("The webhook answered {status}")),
+    }
+}
@@ -0,0 +1,91 @@
+//! Translations of the user interface, loaded from `locales/*.toml`.
+
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, RwLock},
+};
+
+use chrono::Locale;
+use log::error;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Language {
+    English,
+    German,
+    Spanish,
+}
+
+impl Language {
+    pub const ALL: [Self; 3] = [Self::English, Self::German, Self::Spanish];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::English => "English",
+            Self::German => "Deutsch",
+            Self::Spanish => "Español",
+        }
+    }
+
+    pub fn locale(&self) -> Locale {
+        match self {
+            Self::English => Locale::en_US,
+            Self::German => Locale::de_DE,
+            Self::Spanish => Locale::es_ES,
+        }
+    }
+
+    pub fn system() -> Self {
+        let locale = sys_locale::get_locale().unwrap_or_default();
+        match locale.split(['-', '_']).next() {
+            Some("de") => Self::German,
+            Some("es") => Self::Spanish,
+            _ => Self::English,
+        }
+    }
+
+    fn catalog(&self) -> &'static str {
+        match self {
+            Self::English => include_str!("../locales/en.toml"),
+            Self::German => include_str!("../locales/de.toml"),
+            Self::Spanish => include_str!("../locales/es.toml"),
+        }
+    }
+}
+
+static CATALOGS: LazyLock<HashMap<Language, HashMap<String, String>>> = LazyLock::new(|| {
+    Language::ALL
+        .into_iter()
+        .map(|language| {
+            let catalog = toml::from_str(language.catalog()).unwrap_or_else(|e| {
+                error!("Invalid {} message catalog: {e}", language.name());
+                HashMap::new()
+            });
+            (language, catalog)
+        })
+        .collect()
+});
+
+static LANGUAGE: RwLock<Language> = RwLock::new(Language::English);
+
+pub fn language() -> Language {
+    *LANGUAGE.read().unwrap()
+}
+
+pub fn set_language(language: Language) {
+    *LANGUAGE.write().unwrap() = language;
+}
+
+pub fn tr(key: &str) -> String {
+    [language(), Language::English]
+        .iter()
+        .find_map(|language| CATALOGS[language].get(key))
+        .cloned()
+        .unwrap_or_else(|| key.to_owned())
+}
+
+pub fn tr_with(key: &str, args: &[(&str, &str)]) -> String {
+    args.iter().fold(tr(key), |text, (name, value)| {
+        text.replace(&format!("{{{name}}}"), value)
+    })
+}
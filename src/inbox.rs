@@ -0,0 +1,119 @@
+//! Matching emails and finding the verification codes and links in them.
+
+use std::{path::Path, sync::LazyLock};
+
+use regex::Regex;
+use serde_json::json;
+use tmapi::{Attachment, Email};
+
+use crate::dates::iso_date;
+
+static CODE_KEYWORD: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\b(?:code|otp|pin|passcode|password|verification|one-time)\b").unwrap()
+});
+static CODE_TOKEN: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\b[A-Z0-9]{4,10}\b").unwrap());
+static DIGITS: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\b\d{4,8}\b").unwrap());
+pub(crate) static LINK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"https?://[^\s<>"'()\[\]]+"#).unwrap());
+static ACTION_LINK: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)verif|confirm|activat|magic|login|sign.?in|token|reset").unwrap()
+});
+
+/// Matches emails by sender, subject and body.
+#[derive(Default, Clone)]
+pub struct EmailFilter {
+    pub from: Option<Regex>,
+    pub subject: Option<Regex>,
+    pub body: Option<Regex>,
+}
+
+impl EmailFilter {
+    /// Whether the sender and subject match, the body is not checked.
+    pub fn matches_headers(&self, email: &Email) -> bool {
+        let matches =
+            |regex: &Option<Regex>, text: &str| regex.as_ref().is_none_or(|r| r.is_match(text));
+        matches(&self.from, &email.from_address) && matches(&self.subject, &email.subject)
+    }
+
+    pub fn matches(&self, email: &Email) -> bool {
+        self.matches_headers(email)
+            && self
+                .body
+                .as_ref()
+                .is_none_or(|regex| regex.is_match(&body(email)))
+    }
+}
+
+/// The email as JSON for scripts, with the date also in RFC 3339.
+pub fn email_json(email: &Email) -> serde_json::Value {
+    let mut value = serde_json::to_value(email).unwrap_or_default();
+    value["received_at_iso"] = json!(iso_date(email.received_at));
+    value
+}
+
+/// Email JSON with the extracted verification code and link.
+pub fn email_details_json(email: &Email) -> serde_json::Value {
+    let mut value = email_json(email);
+    value["code"] = json!(verification_code(email));
+    value["link"] = json!(verification_link(email));
+    value
+}
+
+/// The attachment's file name without any directories, to save it safely.
+pub fn safe_filename(attachment: &Attachment) -> String {
+    Path::new(&attachment.filename)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| attachment.id.clone())
+}
+
+/// Finds a verification code, preferring one that follows a keyword such as
+/// "code" or "OTP" over any number in the subject or body.
+pub fn verification_code(email: &Email) -> Option<String> {
+    let texts = [email.subject.clone(), body(email)];
+    texts
+        .iter()
+        .find_map(|text| code_after_keyword(text))
+        .or_else(|| texts.iter().find_map(|text| DIGITS.find(text)))
+        .map(|code| code.as_str().to_owned())
+}
+
+/// Finds the link most likely to confirm or sign in, or else the first link.
+pub fn verification_link(email: &Email) -> Option<String> {
+    let texts = [email.text_content.as_deref(), email.html_content.as_deref()];
+    let links = texts
+        .into_iter()
+        .flatten()
+        .flat_map(|text| LINK.find_iter(text))
+        .map(|link| {
+            link.as_str()
+                .trim_end_matches(['.', ',', ';', ':', '!', '?'])
+        })
+        .collect::<Vec<_>>();
+    links
+        .iter()
+        .find(|link| ACTION_LINK.is_match(link))
+        .or(links.first())
+        .map(|link| link.replace("&amp;", "&"))
+}
+
+fn code_after_keyword(text: &str) -> Option<regex::Match<'_>> {
+    CODE_KEYWORD.find_iter(text).find_map(|keyword| {
+        let rest = &text[keyword.end()..];
+        let window = rest
+            .char_indices()
+            .nth(60)
+            .map_or(rest, |(i, _)| &rest[..i]);
+        CODE_TOKEN
+            .find_iter(window)
+            .find(|token| token.as_str().chars().any(|c| c.is_ascii_digit()))
+    })
+}
+
+fn body(email: &Email) -> String {
+    email
+        .text_content
+        .clone()
+        .or_else(|| email.html_content.clone())
+        .unwrap_or_default()
+}
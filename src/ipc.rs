@@ -0,0 +1,329 @@
+use std::{
+    collections::HashSet,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Condvar, Mutex},
+    time::{Duration, Instant},
+};
+
+use eframe::egui;
+use log::warn;
+use rand::distr::{Alphanumeric, SampleString};
+use serde_json::json;
+use subtle::ConstantTimeEq;
+use tiny_http::{Header, Method, Request, Response, Server};
+use tmapi::Email;
+
+use tmgui::{event_handler::Backend, inbox};
+
+pub const TOKEN_FILE: &str = "ipc-token";
+const DEFAULT_WAIT: Duration = Duration::from_secs(60);
+const MAX_WAIT: Duration = Duration::from_secs(120);
+
+type JsonResponse = Response<io::Cursor<Vec<u8>>>;
+
+#[derive(Default)]
+struct Inbox {
+    address: String,
+    addresses: Vec<String>,
+    emails: Vec<Email>,
+    waiting: usize,
+    closed: bool,
+}
+
+struct Shared {
+    inbox: Mutex<Inbox>,
+    changed: Condvar,
+    ctx: egui::Context,
+    /// Fetches email bodies, one request at a time.
+    backend: Mutex<Backend>,
+    /// The app's proxy and API URL, which the backend switches to before a
+    /// request.
+    proxy: Mutex<String>,
+    api_url: Mutex<String>,
+}
+
+/// Localhost HTTP API that lets scripts read the state of the running app.
+pub struct IpcServer {
+    port: u16,
+    server: Arc<Server>,
+    shared: Arc<Shared>,
+}
+
+impl IpcServer {
+    /// Needs a Tokio runtime, like [`Backend::spawn`].
+    pub fn start(
+        ctx: &egui::Context,
+        port: u16,
+        profile_dir: &Path,
+        proxy: &str,
+        api_url: &str,
+    ) -> Result<Self, String> {
+        let token =
+            token(profile_dir).map_err(|e| format!("Could not store the API token: {e}"))?;
+        let server = Server::http(("127.0.0.1", port))
+            .map_err(|e| format!("Could not start the local API on port {port}: {e}"))?;
+        let server = Arc::new(server);
+        let shared = Arc::new(Shared {
+            inbox: Default::default(),
+            changed: Condvar::new(),
+            ctx: ctx.clone(),
+            backend: Mutex::new(Backend::spawn()),
+            proxy: Mutex::new(proxy.to_owned()),
+            api_url: Mutex::new(api_url.to_owned()),
+        });
+
+        let (incoming, state) = (server.clone(), shared.clone());
+        tokio::task::spawn_blocking(move || {
+            for request in incoming.incoming_requests() {
+                let (state, token) = (state.clone(), token.clone());
+                tokio::task::spawn_blocking(move || handle(request, &state, &token));
+            }
+        });
+        Ok(Self {
+            port,
+            server,
+            shared,
+        })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    pub fn token_path(profile_dir: &Path) -> PathBuf {
+        profile_dir.join(TOKEN_FILE)
+    }
+
+    /// Makes the address and its freshly loaded emails visible to clients and
+    /// wakes up long-polls when they changed.
+    pub fn publish(&self, address: &str, emails: &[Email]) {
+        let mut inbox = self.shared.inbox.lock().unwrap();
+        let unchanged = inbox.address == address
+            && inbox.emails.len() == emails.len()
+            && inbox.emails.iter().zip(emails).all(|(a, b)| a.id == b.id);
+        if unchanged {
+            return;
+        }
+        if !inbox.addresses.iter().any(|known| known == address) {
+            inbox.addresses.push(address.to_owned());
+        }
+        inbox.address = address.to_owned();
+        inbox.emails = emails.to_vec();
+        self.shared.changed.notify_all();
+    }
+
+    /// Sends later requests through this proxy, see [`Backend::use_proxy`].
+    pub fn set_proxy(&self, proxy: &str) {
+        *self.shared.proxy.lock().unwrap() = proxy.to_owned();
+    }
+
+    /// Sends later requests to this API, see [`Backend::use_api_url`].
+    pub fn set_api_url(&self, url: &str) {
+        *self.shared.api_url.lock().unwrap() = url.to_owned();
+    }
+
+    /// Whether a client is waiting for new mail, so the app should poll.
+    pub fn waiting(&self) -> bool {
+        self.shared.inbox.lock().unwrap().waiting > 0
+    }
+}
+
+impl Drop for IpcServer {
+    fn drop(&mut self) {
+        self.server.unblock();
+        self.shared.inbox.lock().unwrap().closed = true;
+        self.shared.changed.notify_all();
+    }
+}
+
+/// Reads the token, or replaces it with a new one if it is missing or
+/// others may read it.
+fn token(profile_dir: &Path) -> io::Result<String> {
+    let path = IpcServer::token_path(profile_dir);
+    if exposed(&path) {
+        warn!("{path:?} could be read by other users, replacing the API token");
+    } else if let Ok(token) = fs::read_to_string(&path)
+        && !token.trim().is_empty()
+    {
+        return Ok(token.trim().to_owned());
+    }
+    fs::create_dir_all(profile_dir)?;
+    match fs::remove_file(&path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+        _ => {}
+    }
+    let token = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(&path)?.write_all(token.as_bytes())?;
+    Ok(token)
+}
+
+/// Whether the group or others have any access to the file.
+#[cfg(unix)]
+fn exposed(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path).is_ok_and(|metadata| metadata.permissions().mode() & 0o077 != 0)
+}
+
+#[cfg(not(unix))]
+fn exposed(_path: &Path) -> bool {
+    false
+}
+
+fn handle(request: Request, shared: &Shared, token: &str) {
+    let bearer = format!("Bearer {token}");
+    // In constant time, so the response time tells nothing about the token.
+    let authorized = request.headers().iter().any(|header| {
+        header.field.equiv("Authorization")
+            && bool::from(header.value.as_str().as_bytes().ct_eq(bearer.as_bytes()))
+    });
+    let url = request.url().to_owned();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+
+    let response = if !authorized {
+        error(401, "Missing or wrong token")
+    } else if *request.method() != Method::Get {
+        error(405, "Only GET is supported")
+    } else {
+        match path.trim_end_matches('/') {
+            "/address" => {
+                let inbox = shared.inbox.lock().unwrap();
+                ok(json!({ "address": inbox.address }))
+            }
+            "/addresses" => ok(json!(shared.inbox.lock().unwrap().addresses)),
+            "/emails" => {
+                let inbox = shared.inbox.lock().unwrap();
+                ok(json!(
+                    inbox
+                        .emails
+                        .iter()
+                        .map(inbox::email_json)
+                        .collect::<Vec<_>>()
+                ))
+            }
+            "/emails/latest" => {
+                let latest = shared
+                    .inbox
+                    .lock()
+                    .unwrap()
+                    .emails
+                    .iter()
+                    .max_by_key(|email| email.received_at)
+                    .cloned();
+                match latest {
+                    Some(email) => email_details(shared, email),
+                    None => error(404, "The inbox is empty"),
+                }
+            }
+            "/emails/wait" => wait(shared, query),
+            path => match path.strip_prefix("/emails/") {
+                Some(id) => {
+                    let email = shared
+                        .inbox
+                        .lock()
+                        .unwrap()
+                        .emails
+                        .iter()
+                        .find(|email| email.id == id)
+                        .cloned();
+                    match email {
+                        Some(email) => email_details(shared, email),
+                        None => error(404, "No such email in the inbox"),
+                    }
+                }
+                None => error(404, "Unknown endpoint"),
+            },
+        }
+    };
+    let _ = request.respond(response);
+}
+
+// Blocks until the inbox has emails received after `since`, which defaults to
+// the newest email at the time of the request. Timestamps are in seconds, so
+// emails received in the same second as `since` count too, unless they were
+// already in the inbox when the request came in.
+fn wait(shared: &Shared, query: &str) -> JsonResponse {
+    let param = |name: &str| {
+        query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| *key == name)
+            .and_then(|(_, value)| value.parse::<i64>().ok())
+    };
+    let timeout = param("timeout")
+        .map(|secs| Duration::from_secs(secs.max(0) as u64))
+        .unwrap_or(DEFAULT_WAIT)
+        .min(MAX_WAIT);
+    let deadline = Instant::now() + timeout;
+
+    let mut inbox = shared.inbox.lock().unwrap();
+    let since = param("since").unwrap_or_else(|| {
+        inbox
+            .emails
+            .iter()
+            .map(|email| email.received_at)
+            .max()
+            .unwrap_or(0)
+    });
+    let known = inbox
+        .emails
+        .iter()
+        .filter(|email| email.received_at == since)
+        .map(|email| email.id.clone())
+        .collect::<HashSet<_>>();
+    inbox.waiting += 1;
+    shared.ctx.request_repaint();
+    let new = loop {
+        // Pages loaded with "more" can overlap, so each id is listed once.
+        let mut listed = HashSet::new();
+        let new = inbox
+            .emails
+            .iter()
+            .filter(|email| {
+                email.received_at > since
+                    || (email.received_at == since && !known.contains(&email.id))
+            })
+            .filter(|email| listed.insert(&email.id))
+            .map(inbox::email_json)
+            .collect::<Vec<_>>();
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if !new.is_empty() || remaining.is_zero() || inbox.closed {
+            break new;
+        }
+        inbox = shared.changed.wait_timeout(inbox, remaining).unwrap().0;
+    };
+    inbox.waiting -= 1;
+
+    match new.is_empty() {
+        true => Response::from_data(Vec::new()).with_status_code(204),
+        false => ok(json!(new)),
+    }
+}
+
+fn email_details(shared: &Shared, email: Email) -> JsonResponse {
+    let proxy = shared.proxy.lock().unwrap().clone();
+    let api_url = shared.api_url.lock().unwrap().clone();
+    let mut backend = shared.backend.lock().unwrap();
+    let fetched = backend
+        .use_proxy(&proxy)
+        .and_then(|()| backend.use_api_url(&api_url))
+        .and_then(|()| backend.request_full_email(email));
+    match fetched {
+        Ok(email) => ok(inbox::email_details_json(&email)),
+        Err(message) => error(502, &message),
+    }
+}
+
+fn ok(value: serde_json::Value) -> JsonResponse {
+    Response::from_string(value.to_string())
+        .with_header("Content-Type: application/json".parse::<Header>().unwrap())
+}
+
+fn error(status: u16, message: &str) -> JsonResponse {
+    ok(json!({ "error": message })).with_status_code(status)
+}
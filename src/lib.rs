@@ -0,0 +1,45 @@
+//! Core of tmgui, a client for the barid.site temporary email service.
+//!
+//! Frontends talk to the API through the [`event_handler`] backend: they
+//! send [`Event`](event_handler::Event)s and feed the
+//! [`EventResponse`](event_handler::EventResponse)s into an
+//! [`InboxState`](state::InboxState). [`inbox`] finds matching emails and the
+//! verification codes and links in them, [`links`] and [`attachments`] flag
+//! suspicious links and files, [`archives`] and [`previews`] look inside
+//! attachments, [`cards`] reads calendar invites and contacts in them,
+//! [`rules`] triage new emails, [`hooks`] hand them to scripts and
+//! [`forward`] sends them on over SMTP. [`retention`] and [`vault`] hold the
+//! per-address cleanup settings and their encrypted storage.
+//!
+//! ```no_run
+//! # #[tokio::main]
+//! # async fn main() {
+//! use tmgui::event_handler::{Backend, Event, EventResponse};
+//!
+//! let backend = Backend::spawn();
+//! if let Ok(EventResponse::Emails(_, emails)) =
+//!     backend.request(Event::FetchEmails("user@vwh.sh".into()))
+//! {
+//!     for email in emails {
+//!         println!("{}", email.subject);
+//!     }
+//! }
+//! # }
+//! ```
+
+pub mod archives;
+pub mod attachments;
+pub mod cards;
+pub mod dates;
+pub mod event_handler;
+pub mod forward;
+pub mod hooks;
+pub mod i18n;
+pub mod inbox;
+pub mod links;
+pub mod previews;
+pub mod retention;
+pub mod rules;
+pub mod state;
+pub mod units;
+pub mod vault;
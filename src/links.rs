@@ -0,0 +1,251 @@
+//! The links in an email and what is suspicious about them.
+
+use std::{net::IpAddr, sync::LazyLock};
+
+use regex::Regex;
+use reqwest::Url;
+use tmapi::Email;
+
+use crate::{
+    i18n::{tr, tr_with},
+    inbox::LINK,
+};
+
+static ANCHOR: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?is)<a\b[^>]*?\bhref\s*=\s*(?:"([^"]*)"|'([^']*)')[^>]*>(.*?)</a\s*>"#).unwrap()
+});
+static TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<[^>]*>").unwrap());
+static DOMAIN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)(?:[\p{L}\p{N}-]+\.)+[\p{L}]{2,}").unwrap());
+
+/// Query parameters that only track the click.
+const TRACKING_PARAMS: [&str; 18] = [
+    "fbclid",
+    "gclid",
+    "dclid",
+    "gbraid",
+    "wbraid",
+    "msclkid",
+    "yclid",
+    "igshid",
+    "mc_cid",
+    "mc_eid",
+    "_hsenc",
+    "_hsmi",
+    "mkt_tok",
+    "_ga",
+    "_gl",
+    "vero_id",
+    "oly_anon_id",
+    "oly_enc_id",
+];
+
+/// Latin lookalikes from other scripts, mapped to the letter they imitate.
+const CONFUSABLES: [(char, char); 24] = [
+    ('а', 'a'),
+    ('е', 'e'),
+    ('о', 'o'),
+    ('р', 'p'),
+    ('с', 'c'),
+    ('х', 'x'),
+    ('у', 'y'),
+    ('і', 'i'),
+    ('ј', 'j'),
+    ('ѕ', 's'),
+    ('ԁ', 'd'),
+    ('һ', 'h'),
+    ('ӏ', 'l'),
+    ('ɡ', 'g'),
+    ('ο', 'o'),
+    ('α', 'a'),
+    ('ν', 'v'),
+    ('κ', 'k'),
+    ('τ', 't'),
+    ('ι', 'i'),
+    ('ρ', 'p'),
+    ('ʏ', 'y'),
+    ('ɑ', 'a'),
+    ('ı', 'i'),
+];
+
+pub enum LinkWarning {
+    /// The text shows another domain than the link goes to.
+    TextMismatch(String),
+    /// The domain is internationalized, with its Unicode form.
+    Punycode(String),
+    /// The domain imitates this ASCII one with letters from other scripts.
+    Lookalike(String),
+    IpAddress,
+    /// A user name before the host, as in `https://bank.com@evil.com`.
+    Credentials,
+}
+
+impl LinkWarning {
+    pub fn describe(&self) -> String {
+        match self {
+            Self::TextMismatch(shown) => tr_with("link_text_mismatch", &[("shown", shown)]),
+            Self::Punycode(unicode) => tr_with("link_punycode", &[("domain", unicode)]),
+            Self::Lookalike(imitated) => tr_with("link_lookalike", &[("domain", imitated)]),
+            Self::IpAddress => tr("link_ip_address"),
+            Self::Credentials => tr("link_credentials"),
+        }
+    }
+}
+
+pub struct Link {
+    pub url: String,
+    /// The text of an HTML link.
+    pub text: Option<String>,
+    pub warnings: Vec<LinkWarning>,
+}
+
+impl Link {
+    pub fn new(url: String, text: Option<String>) -> Self {
+        let warnings = check(&url, text.as_deref());
+        Self {
+            url,
+            text,
+            warnings,
+        }
+    }
+
+    pub fn host(&self) -> Option<String> {
+        Url::parse(&self.url).ok()?.host_str().map(str::to_owned)
+    }
+}
+
+/// The web links of the HTML body with their text, then those only in the
+/// text body.
+pub fn links(email: &Email) -> Vec<Link> {
+    let mut links = Vec::new();
+    for anchor in email
+        .html_content
+        .iter()
+        .flat_map(|html| ANCHOR.captures_iter(html))
+    {
+        let href = anchor.get(1).or(anchor.get(2)).map_or("", |m| m.as_str());
+        let url = decode_entities(href.trim());
+        if !is_web(&url) {
+            continue;
+        }
+        let text = decode_entities(&TAG.replace_all(&anchor[3], " "));
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        let text = (!text.is_empty()).then_some(text);
+        if !links
+            .iter()
+            .any(|link: &Link| link.url == url && link.text == text)
+        {
+            links.push(Link::new(url, text));
+        }
+    }
+    for found in email
+        .text_content
+        .iter()
+        .flat_map(|text| LINK.find_iter(text))
+    {
+        let url = found
+            .as_str()
+            .trim_end_matches(['.', ',', ';', ':', '!', '?']);
+        if !links.iter().any(|link| link.url == url) {
+            links.push(Link::new(url.to_owned(), None));
+        }
+    }
+    links
+}
+
+/// What is suspicious about a link with this text.
+pub fn check(url: &str, text: Option<&str>) -> Vec<LinkWarning> {
+    let mut warnings = Vec::new();
+    let Ok(parsed) = Url::parse(url) else {
+        return warnings;
+    };
+    if !parsed.username().is_empty() || parsed.password().is_some() {
+        warnings.push(LinkWarning::Credentials);
+    }
+    let Some(host) = parsed.host_str() else {
+        return warnings;
+    };
+    if host.trim_matches(['[', ']']).parse::<IpAddr>().is_ok() {
+        warnings.push(LinkWarning::IpAddress);
+        return warnings;
+    }
+    let (unicode, _) = idna::domain_to_unicode(host);
+    if unicode != host {
+        warnings.push(LinkWarning::Punycode(unicode.clone()));
+        if let Some(imitated) = imitated(&unicode) {
+            warnings.push(LinkWarning::Lookalike(imitated));
+        }
+    }
+    if let Some(shown) = text.and_then(|text| DOMAIN.find(text))
+        && !same_site(shown.as_str(), host)
+    {
+        warnings.push(LinkWarning::TextMismatch(shown.as_str().to_owned()));
+    }
+    warnings
+}
+
+/// Removes the `utm_*` and other click tracking parameters.
+pub fn strip_tracking(url: &str) -> String {
+    let Ok(mut parsed) = Url::parse(url) else {
+        return url.to_owned();
+    };
+    let pairs = parsed
+        .query_pairs()
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect::<Vec<_>>();
+    let kept = pairs
+        .iter()
+        .filter(|(key, _)| {
+            let key = key.to_lowercase();
+            !key.starts_with("utm_") && !TRACKING_PARAMS.contains(&key.as_str())
+        })
+        .collect::<Vec<_>>();
+    if kept.len() == pairs.len() {
+        return url.to_owned();
+    }
+    if kept.is_empty() {
+        parsed.set_query(None);
+    } else {
+        parsed.query_pairs_mut().clear().extend_pairs(kept);
+    }
+    parsed.into()
+}
+
+fn is_web(url: &str) -> bool {
+    let lower = url.to_lowercase();
+    lower.starts_with("https://") || lower.starts_with("http://")
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+/// The ASCII domain a Unicode domain imitates, if all its non-ASCII letters
+/// are lookalikes.
+fn imitated(unicode: &str) -> Option<String> {
+    unicode
+        .chars()
+        .map(|c| match c.is_ascii() {
+            true => Some(c),
+            false => CONFUSABLES
+                .iter()
+                .find(|(lookalike, _)| *lookalike == c)
+                .map(|(_, latin)| *latin),
+        })
+        .collect()
+}
+
+/// Whether the domains are equal or one is a subdomain of the other.
+fn same_site(shown: &str, host: &str) -> bool {
+    let normalize = |domain: &str| {
+        let domain = idna::domain_to_ascii(domain).unwrap_or_else(|_| domain.to_lowercase());
+        domain.trim_start_matches("www.").to_owned()
+    };
+    let (shown, host) = (normalize(shown), normalize(host));
+    shown == host || host.ends_with(&format!(".{shown}")) || shown.ends_with(&format!(".{host}"))
+}
@@ -1,39 +1,74 @@
-use std::sync::mpsc::channel;
-
+use clap::Parser;
 use eframe::egui::ViewportBuilder;
-use tokio::task::spawn_blocking;
+use log::warn;
+
+use tmgui::event_handler::Handler;
 
 use crate::{
     app::TempMailApp,
-    event_handler::{Event, EventResponse, Handler},
+    cli::{APP_ID, Args},
+    config::state::STATE_FILE,
 };
 
 mod app;
+mod cli;
 mod config;
-mod event_handler;
+mod headless;
+mod ipc;
+mod tui;
 #[tokio::main]
 async fn main() -> eframe::Result {
     pretty_env_logger::init();
+    let mut args = Args::parse();
+    if let Some(command) = args.command.take() {
+        std::process::exit(headless::run(command, &args));
+    }
+    if args.tui {
+        if let Err(e) = tui::run(&args) {
+            eprintln!("error: {e}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    let persistence_path = match (&args.profile, args.profile_dir()) {
+        (Some(_), Some(dir)) if args.private => Some(dir.join(STATE_FILE)),
+        (Some(_), Some(dir)) => match std::fs::create_dir_all(&dir) {
+            Ok(()) => Some(dir.join(STATE_FILE)),
+            Err(e) => {
+                warn!("Could not create profile directory: {e:?}");
+                None
+            }
+        },
+        _ => None,
+    };
+    let (state, load_error) = args.start_state();
+    let cli_config = args.cli_config();
     let opts = eframe::NativeOptions {
-        viewport: ViewportBuilder::default().with_app_id("adenosine.tmgui"),
+        viewport: state
+            .window
+            .builder(ViewportBuilder::default().with_app_id(APP_ID)),
+        persistence_path,
+        // The window size is part of the saved state, and eframe would write
+        // a wiped state back together with the window position.
+        persist_window: false,
         ..Default::default()
     };
-    let (tx_event, rx_event) = channel::<Event>();
-    let (tx_response, rx_response) = channel::<EventResponse>();
-    let handler = Handler::new(rx_event, tx_response);
-    spawn_blocking(move || handler.listen());
+    let (tx_event, rx_response) = Handler::spawn();
+    let profile_dir = args.profile_dir();
+    let private = args.private;
 
     eframe::run_native(
         "TMApi",
         opts,
-        Box::new(|c| {
-            let name = c.storage.and_then(|x| x.get_string("name"));
-            let domain = c.storage.and_then(|x| x.get_string("domain"));
+        Box::new(move |_| {
             Ok(Box::new(TempMailApp::new(
                 tx_event,
                 rx_response,
-                name,
-                domain,
+                state,
+                cli_config,
+                profile_dir,
+                private,
+                load_error,
             )))
         }),
     )
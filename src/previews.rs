@@ -0,0 +1,147 @@
+//! Reading text, CSV, JSON and PDF attachments for viewing them in the app.
+
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+
+use serde_json::Value;
+use tmapi::Attachment;
+
+/// Longer files are cut off.
+pub const MAX_TEXT_LEN: usize = 1 << 20;
+/// Larger JSON and PDF files are not shown, of CSV files only the start.
+pub const MAX_FILE_LEN: u64 = 32 << 20;
+/// Further CSV rows are left out.
+pub const MAX_ROWS: usize = 5000;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format {
+    Text,
+    Csv,
+    Json,
+    Pdf,
+}
+
+impl Format {
+    /// The viewer for an attachment judging by its name and MIME type.
+    pub fn of(attachment: &Attachment) -> Option<Self> {
+        let extension = attachment
+            .filename
+            .rsplit_once('.')
+            .map(|(_, extension)| extension.to_lowercase())
+            .unwrap_or_default();
+        let content_type = attachment.content_type.to_lowercase();
+        match extension.as_str() {
+            "txt" | "log" | "md" | "ini" | "cfg" | "conf" | "yaml" | "yml" | "toml" | "xml" => {
+                Some(Self::Text)
+            }
+            "csv" | "tsv" => Some(Self::Csv),
+            "json" | "ndjson" => Some(Self::Json),
+            "pdf" => Some(Self::Pdf),
+            _ if content_type == "text/csv" => Some(Self::Csv),
+            _ if content_type.contains("json") => Some(Self::Json),
+            _ if content_type == "application/pdf" => Some(Self::Pdf),
+            _ if content_type.starts_with("text/plain") => Some(Self::Text),
+            _ => None,
+        }
+    }
+}
+
+pub enum Preview {
+    Text(String),
+    /// The first row is usually the header.
+    Table(Vec<Vec<String>>),
+    Json(Value),
+    /// The text of each page.
+    Pdf(Vec<String>),
+}
+
+impl Preview {
+    /// Reads a downloaded attachment, at most [`MAX_FILE_LEN`] of it.
+    pub fn load(path: &Path, format: Format) -> Result<Self, String> {
+        let read_error = |e: io::Error| format!("Could not read {}: {e}", path.display());
+        let file = File::open(path).map_err(read_error)?;
+        let size = file.metadata().map_err(read_error)?.len();
+        if size > MAX_FILE_LEN && matches!(format, Format::Json | Format::Pdf) {
+            return Err(format!(
+                "{} is too large to preview",
+                path.file_name().unwrap_or_default().to_string_lossy()
+            ));
+        }
+        let len = match format {
+            Format::Text => MAX_TEXT_LEN as u64 + 1,
+            _ => MAX_FILE_LEN,
+        };
+        let mut bytes = Vec::new();
+        file.take(len).read_to_end(&mut bytes).map_err(read_error)?;
+        match format {
+            Format::Text => Ok(Self::Text(text(&bytes))),
+            Format::Csv => table(&bytes).map(Self::Table),
+            Format::Json => serde_json::from_slice(&bytes)
+                .or_else(|_| json_lines(&bytes))
+                .map(Self::Json)
+                .map_err(|e| format!("Invalid JSON: {e}")),
+            Format::Pdf => pdf_pages(&bytes).map(Self::Pdf),
+        }
+    }
+}
+
+fn text(bytes: &[u8]) -> String {
+    let mut text = String::from_utf8_lossy(&bytes[..bytes.len().min(MAX_TEXT_LEN)]).into_owned();
+    if bytes.len() > MAX_TEXT_LEN {
+        text.push_str("\n…");
+    }
+    text
+}
+
+fn table(bytes: &[u8]) -> Result<Vec<Vec<String>>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(delimiter(bytes))
+        .from_reader(bytes);
+    reader
+        .byte_records()
+        .take(MAX_ROWS)
+        .map(|record| {
+            let record = record.map_err(|e| format!("Invalid CSV: {e}"))?;
+            Ok(record
+                .iter()
+                .map(|field| String::from_utf8_lossy(field).into_owned())
+                .collect())
+        })
+        .collect()
+}
+
+/// The most common of comma, semicolon and tab in the first line, as
+/// spreadsheets in many locales export with semicolons.
+fn delimiter(bytes: &[u8]) -> u8 {
+    let line = bytes
+        .split(|&byte| byte == b'\n')
+        .next()
+        .unwrap_or_default();
+    [b',', b';', b'\t']
+        .into_iter()
+        .max_by_key(|&delimiter| line.iter().filter(|&&byte| byte == delimiter).count())
+        .filter(|&delimiter| line.contains(&delimiter))
+        .unwrap_or(b',')
+}
+
+/// Newline delimited JSON as an array.
+fn json_lines(bytes: &[u8]) -> serde_json::Result<Value> {
+    serde_json::Deserializer::from_slice(bytes)
+        .into_iter()
+        .collect::<serde_json::Result<_>>()
+        .map(Value::Array)
+}
+
+fn pdf_pages(bytes: &[u8]) -> Result<Vec<String>, String> {
+    let document = lopdf::Document::load_mem(bytes).map_err(|e| format!("Invalid PDF: {e}"))?;
+    Ok(document
+        .get_pages()
+        .into_keys()
+        .map(|page| document.extract_text(&[page]).unwrap_or_default())
+        .collect())
+}
@@ -0,0 +1,45 @@
+//! Deleting the emails of an address automatically.
+
+use serde::{Deserialize, Serialize};
+
+/// The address whose inbox was loaded last, to clear it on leaving. Only a
+/// loaded inbox counts as opened, not every state of the address field
+/// while the user types.
+#[derive(Default)]
+pub struct OpenedInbox {
+    address: Option<String>,
+}
+
+impl OpenedInbox {
+    /// Records that the emails of `address` were loaded. Returns the address
+    /// open before if it is another one, which was left.
+    pub fn open(&mut self, address: &str) -> Option<String> {
+        match self.address.replace(address.to_owned()) {
+            Some(left) if left != address => Some(left),
+            _ => None,
+        }
+    }
+}
+
+/// What to delete automatically from an address.
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Retention {
+    /// Emails older than this are deleted, 0 keeps them.
+    pub max_age_hours: u32,
+    /// Deletes an email when it is closed after reading.
+    pub burn_after_reading: bool,
+    /// Deletes all emails when switching to another address.
+    pub clear_on_leave: bool,
+}
+
+impl Retention {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Whether an email received at this Unix timestamp is too old.
+    pub fn expired(&self, received_at: i64, now: i64) -> bool {
+        self.max_age_hours > 0 && now - received_at > i64::from(self.max_age_hours) * 3600
+    }
+}
@@ -0,0 +1,351 @@
+//! Rules that triage newly arrived emails.
+//!
+//! A [`Rule`] matches emails by sender, subject, body and attachments and
+//! lists [`RuleAction`]s. The [`RuleRunner`] evaluates rules off the UI
+//! thread, fetching bodies and attachments as the rules need them. It deletes
+//! and downloads itself and reports the other actions to the frontend.
+
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tmapi::{Attachment, Email};
+
+use crate::{
+    attachments::{check_file, declared_risk},
+    event_handler::{Backend, Event, EventResponse, Job, Worker},
+    i18n::{tr, tr_with},
+    inbox::{self, EmailFilter, safe_filename},
+    links::{self, Link},
+};
+
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum AttachmentCondition {
+    #[default]
+    Any,
+    With,
+    Without,
+}
+
+impl AttachmentCondition {
+    pub const ALL: [Self; 3] = [Self::Any, Self::With, Self::Without];
+
+    pub fn name(&self) -> String {
+        match self {
+            Self::Any => tr("rule_attachments_any"),
+            Self::With => tr("rule_attachments_with"),
+            Self::Without => tr("rule_attachments_without"),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub enum RuleAction {
+    Delete,
+    Label(String),
+    MarkRead,
+    Notify,
+    CopyCode,
+    OpenLink,
+    DownloadAttachments,
+}
+
+impl RuleAction {
+    /// One of each action, with an empty label.
+    pub fn all() -> [Self; 7] {
+        [
+            Self::Delete,
+            Self::Label(String::new()),
+            Self::MarkRead,
+            Self::Notify,
+            Self::CopyCode,
+            Self::OpenLink,
+            Self::DownloadAttachments,
+        ]
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Self::Delete => tr("rule_action_delete"),
+            Self::Label(label) if label.is_empty() => tr("rule_action_label_empty"),
+            Self::Label(label) => tr_with("rule_action_label", &[("label", label)]),
+            Self::MarkRead => tr("rule_action_mark_read"),
+            Self::Notify => tr("rule_action_notify"),
+            Self::CopyCode => tr("rule_action_copy_code"),
+            Self::OpenLink => tr("rule_action_open_link"),
+            Self::DownloadAttachments => tr("rule_action_download"),
+        }
+    }
+}
+
+/// Conditions that all have to hold and the actions to take. Empty patterns
+/// match everything.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Rule {
+    pub name: String,
+    pub enabled: bool,
+    /// Regexes for the sender, the subject and the body.
+    pub from: String,
+    pub subject: String,
+    pub body: String,
+    pub attachments: AttachmentCondition,
+    /// Comma separated file extensions or MIME types, one attachment has to
+    /// match one of them.
+    pub attachment_types: String,
+    pub actions: Vec<RuleAction>,
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            enabled: true,
+            from: String::new(),
+            subject: String::new(),
+            body: String::new(),
+            attachments: AttachmentCondition::Any,
+            attachment_types: String::new(),
+            actions: Vec::new(),
+        }
+    }
+}
+
+impl Rule {
+    /// Compiles the patterns, describing the first invalid one.
+    pub fn filter(&self) -> Result<EmailFilter, String> {
+        let compile = |field: &str, pattern: &str| match pattern.trim() {
+            "" => Ok(None),
+            pattern => Regex::new(pattern)
+                .map(Some)
+                .map_err(|e| format!("{}: {field}: {e}", self.name)),
+        };
+        Ok(EmailFilter {
+            from: compile("from", &self.from)?,
+            subject: compile("subject", &self.subject)?,
+            body: compile("body", &self.body)?,
+        })
+    }
+
+    /// Whether the email matches, given its attachments if the rule
+    /// needs them.
+    pub fn matches(&self, filter: &EmailFilter, email: &Email, attachments: &[Attachment]) -> bool {
+        let attachments_match = match self.attachments {
+            AttachmentCondition::Any => true,
+            AttachmentCondition::With => email.has_attachments,
+            AttachmentCondition::Without => !email.has_attachments,
+        };
+        let types = self.types();
+        let types_match = types.is_empty()
+            || attachments.iter().any(|attachment| {
+                let filename = attachment.filename.to_lowercase();
+                let content_type = attachment.content_type.to_lowercase();
+                types
+                    .iter()
+                    .any(|kind| content_type == *kind || filename.ends_with(&format!(".{kind}")))
+            });
+        attachments_match && types_match && filter.matches(email)
+    }
+
+    fn types(&self) -> Vec<String> {
+        self.attachment_types
+            .split(',')
+            .map(|kind| kind.trim().trim_start_matches('.').to_lowercase())
+            .filter(|kind| !kind.is_empty())
+            .collect()
+    }
+
+    fn needs_body(&self) -> bool {
+        !self.body.trim().is_empty()
+            || self
+                .actions
+                .iter()
+                .any(|action| matches!(action, RuleAction::CopyCode | RuleAction::OpenLink))
+    }
+
+    fn needs_attachments(&self) -> bool {
+        !self.types().is_empty() || self.actions.contains(&RuleAction::DownloadAttachments)
+    }
+}
+
+/// Emails to evaluate the rules on.
+pub struct RuleJob {
+    pub emails: Vec<Email>,
+    pub rules: Vec<Rule>,
+    /// Only reports what would happen.
+    pub dry_run: bool,
+    /// See [`Event::SetProxy`].
+    pub proxy: String,
+    /// See [`Event::SetApiUrl`].
+    pub api_url: String,
+    pub download_dir: PathBuf,
+    /// Larger attachments are not downloaded, 0 for no limit.
+    pub max_download: u64,
+}
+
+/// A rule that matched an email.
+pub struct RuleOutcome {
+    pub email: Email,
+    pub rule: String,
+    pub actions: Vec<RuleAction>,
+    pub code: Option<String>,
+    /// The first web link, for [`RuleAction::OpenLink`].
+    pub link: Option<Link>,
+}
+
+/// The result of a [`RuleJob`].
+pub struct RuleReport {
+    pub dry_run: bool,
+    pub outcomes: Vec<RuleOutcome>,
+    pub errors: Vec<String>,
+}
+
+/// Evaluates [`RuleJob`]s off the UI thread.
+pub type RuleRunner = Worker<RuleJob>;
+
+impl Job for RuleJob {
+    type Report = RuleReport;
+
+    fn proxy(&self) -> &str {
+        &self.proxy
+    }
+
+    fn api_url(&self) -> &str {
+        &self.api_url
+    }
+
+    fn run(self, backend: &Backend) -> RuleReport {
+        run(backend, self)
+    }
+
+    fn failed(self, error: String) -> RuleReport {
+        RuleReport {
+            dry_run: self.dry_run,
+            outcomes: Vec::new(),
+            errors: vec![error],
+        }
+    }
+}
+
+fn run(backend: &Backend, job: RuleJob) -> RuleReport {
+    let mut report = RuleReport {
+        dry_run: job.dry_run,
+        outcomes: Vec::new(),
+        errors: Vec::new(),
+    };
+    let mut rules = Vec::new();
+    for rule in job.rules.iter().filter(|rule| rule.enabled) {
+        match rule.filter() {
+            Ok(filter) => rules.push((rule, filter)),
+            Err(e) => report.errors.push(e),
+        }
+    }
+    for mut email in job.emails {
+        let mut attachments: Option<Vec<Attachment>> = None;
+        for (rule, filter) in &rules {
+            if !filter.matches_headers(&email) {
+                continue;
+            }
+            if rule.needs_body() {
+                match backend.request_full_email(email.clone()) {
+                    Ok(full) => email = full,
+                    Err(e) => {
+                        report.errors.push(e);
+                        continue;
+                    }
+                }
+            }
+            if rule.needs_attachments() && attachments.is_none() && email.has_attachments {
+                match backend.request(Event::GetAttachments(email.id.clone())) {
+                    Ok(EventResponse::Attachments(list)) => attachments = Some(list),
+                    Ok(_) => {}
+                    Err(e) => {
+                        report.errors.push(e);
+                        continue;
+                    }
+                }
+            }
+            let attachments = attachments.as_deref().unwrap_or_default();
+            if !rule.matches(filter, &email, attachments) {
+                continue;
+            }
+            if !job.dry_run {
+                perform(
+                    backend,
+                    &job.download_dir,
+                    job.max_download,
+                    rule,
+                    &email,
+                    attachments,
+                    &mut report,
+                );
+            }
+            report.outcomes.push(RuleOutcome {
+                email: email.clone(),
+                rule: rule.name.clone(),
+                actions: rule.actions.clone(),
+                code: inbox::verification_code(&email),
+                link: links::links(&email).into_iter().next(),
+            });
+            if rule.actions.contains(&RuleAction::Delete) {
+                break;
+            }
+        }
+    }
+    report
+}
+
+/// Takes the actions that need the backend.
+///
+/// Attachments that are too large or look dangerous by name or content are
+/// not kept, as nobody is asked before they are downloaded.
+fn perform(
+    backend: &Backend,
+    download_dir: &Path,
+    max_download: u64,
+    rule: &Rule,
+    email: &Email,
+    attachments: &[Attachment],
+    report: &mut RuleReport,
+) {
+    if rule.actions.contains(&RuleAction::DownloadAttachments) {
+        for attachment in attachments {
+            let name = &attachment.filename;
+            if max_download > 0 && attachment.size > max_download {
+                report.errors.push(format!(
+                    "Skipped {name}, it is larger than the download limit"
+                ));
+                continue;
+            }
+            if let Some(risk) = declared_risk(attachment) {
+                report
+                    .errors
+                    .push(format!("Skipped {name}: {}", risk.describe()));
+                continue;
+            }
+            let path = download_dir.join(safe_filename(attachment));
+            let event =
+                Event::DownloadAttachment(attachment.id.clone(), path.clone(), max_download);
+            if let Err(e) = backend.request(event) {
+                report.errors.push(e);
+                continue;
+            }
+            match check_file(&path) {
+                Ok(check) => {
+                    if let Some(risk) = check.risk {
+                        let _ = std::fs::remove_file(&path);
+                        report
+                            .errors
+                            .push(format!("Deleted {name}: {}", risk.describe()));
+                    }
+                }
+                Err(e) => report.errors.push(format!("Could not check {name}: {e}")),
+            }
+        }
+    }
+    if rule.actions.contains(&RuleAction::Delete)
+        && let Err(e) = backend.request(Event::DeleteEmail(email.id.clone()))
+    {
+        report.errors.push(e);
+    }
+}
@@ -0,0 +1,67 @@
+//! The inbox state that frontends build from [`EventResponse`]s.
+
+use rand::Rng;
+use tmapi::{Attachment, Email};
+
+use crate::event_handler::EventResponse;
+
+/// The domain used when none was chosen or stored.
+pub const DEFAULT_DOMAIN: &str = "vwh.sh";
+
+/// A random alphanumeric name for a new address.
+pub fn random_name(len: usize) -> String {
+    rand::rng()
+        .sample_iter(rand::distr::Alphanumeric)
+        .take(len)
+        .map(|x| x as char)
+        .collect::<String>()
+}
+
+/// The address being watched and what the backend reported about it.
+#[derive(Default)]
+pub struct InboxState {
+    pub name: String,
+    pub domain: String,
+    pub domains: Vec<String>,
+    pub emails: Vec<Email>,
+    /// Total number of emails on the server, which may exceed `emails.len()`.
+    pub email_count: u32,
+    pub current_offset: u32,
+    pub viewed_email: Option<Email>,
+    /// Attachments of the viewed email, once requested.
+    pub attachments: Option<Vec<Attachment>>,
+}
+
+impl InboxState {
+    pub fn new(name: String, domain: String) -> Self {
+        Self {
+            name,
+            domain,
+            ..Default::default()
+        }
+    }
+
+    pub fn address(&self) -> String {
+        [self.name.clone(), self.domain.clone()].join("@")
+    }
+
+    /// Updates the state with a response, returning the message of errors.
+    pub fn apply(&mut self, response: EventResponse) -> Result<(), String> {
+        match response {
+            EventResponse::Domains(domains) => self.domains = domains,
+            EventResponse::Emails(_, emails) => self.emails = emails,
+            EventResponse::Email(email) => self.viewed_email = Some(email),
+            EventResponse::Count(c) => self.email_count = c,
+            EventResponse::EmailsMore(emails) => self.emails.extend(emails),
+            EventResponse::EmailsDeleted => self.emails.clear(),
+            EventResponse::Attachments(attachments) => self.attachments = Some(attachments),
+            // By id, as the list may have been reloaded since the request.
+            EventResponse::EmailDeleted(id) => self.emails.retain(|email| email.id != id),
+            EventResponse::AttachmentSaved(_)
+            | EventResponse::ProxySet
+            | EventResponse::ApiUrlSet => {}
+            EventResponse::Error(message) => return Err(message),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,543 @@
+use std::{
+    io,
+    path::Path,
+    sync::mpsc::{Receiver, Sender},
+    time::{Duration, Instant},
+};
+
+use ratatui::{
+    DefaultTerminal, Frame,
+    crossterm::{
+        event::{self, KeyCode, KeyEventKind, KeyModifiers},
+        terminal,
+    },
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Clear, List, ListItem, ListState, Paragraph, Wrap},
+};
+use tempfile::TempDir;
+use tmapi::Email;
+use tmgui::{
+    attachments::check_file,
+    event_handler::{Event, EventResponse, Handler},
+    i18n::{self, Language, tr, tr_with},
+    inbox::{self, safe_filename},
+    state::{DEFAULT_DOMAIN, InboxState, random_name},
+    units::UnitSize,
+    vault::VaultKey,
+};
+use zeroize::Zeroizing;
+
+use crate::{
+    cli::Args,
+    config::{
+        Settings,
+        file::{ConfigFile, ConfigWatcher},
+        state::SavedState,
+    },
+};
+
+const TICK: Duration = Duration::from_millis(200);
+const UNLOCK_ATTEMPTS: usize = 3;
+
+struct Tui {
+    inbox: InboxState,
+    settings: Settings,
+    list: ListState,
+    scroll: u16,
+    domain_picker: Option<ListState>,
+    fetching: bool,
+    polling: bool,
+    last_poll: Instant,
+    status: Option<String>,
+    /// Downloads go here in private mode, deleted on drop.
+    private: Option<TempDir>,
+    cli_config: ConfigFile,
+    config_watcher: ConfigWatcher,
+    events: Sender<Event>,
+    responses: Receiver<EventResponse>,
+}
+
+pub fn run(args: &Args) -> io::Result<()> {
+    let (mut state, load_error) = args.start_state();
+    let (config, errors) = args.config();
+    let mut settings = state.settings.clone();
+    settings.overlay = config;
+    i18n::set_language(settings.language.unwrap_or_else(Language::system));
+    let key = unlock(&mut state)?;
+    let (events, responses) = Handler::spawn();
+    let mut tui = Tui {
+        inbox: InboxState::new(
+            state.name.clone().unwrap_or_else(|| random_name(10)),
+            state
+                .domain
+                .clone()
+                .or(settings.default_domain())
+                .unwrap_or_else(|| DEFAULT_DOMAIN.into()),
+        ),
+        settings,
+        list: Default::default(),
+        scroll: 0,
+        domain_picker: None,
+        fetching: false,
+        polling: false,
+        last_poll: Instant::now(),
+        status: load_error.or(config_status(errors)),
+        private: match args.private {
+            true => Some(
+                tempfile::Builder::new()
+                    .prefix("tmgui-private-")
+                    .tempdir()?,
+            ),
+            false => None,
+        },
+        cli_config: args.cli_config(),
+        config_watcher: ConfigWatcher::new(),
+        events,
+        responses,
+    };
+    tui.send_event(Event::SetProxy(tui.settings.proxy().into()));
+    tui.send_event(Event::SetApiUrl(tui.settings.api_url().into()));
+    tui.send_event(Event::FetchDomanins);
+    tui.refresh();
+
+    // Log lines on stderr would draw over the screen, errors show in the
+    // status line instead.
+    log::set_max_level(log::LevelFilter::Off);
+    let mut terminal = ratatui::init();
+    let result = tui.run(&mut terminal);
+    ratatui::restore();
+    state.name = Some(tui.inbox.name.clone());
+    state.domain = Some(tui.inbox.domain.clone());
+    if !args.private {
+        state.remember_address(tui.inbox.address());
+        if let Some(key) = &key {
+            state.seal(key);
+        }
+        args.store_state(&state)?;
+    }
+    result
+}
+
+impl Tui {
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        loop {
+            self.handle_responses();
+            self.watch_config();
+            self.poll();
+            terminal.draw(|frame| self.draw(frame))?;
+            if !event::poll(TICK)? {
+                continue;
+            }
+            if let event::Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+                && !self.handle_key(key.code)
+            {
+                return Ok(());
+            }
+        }
+    }
+
+    fn send_event(&mut self, event: Event) {
+        self.fetching = true;
+        let _ = self.events.send(event);
+    }
+
+    fn refresh(&mut self) {
+        self.send_event(Event::FetchEmails(self.inbox.address()));
+        self.send_event(Event::CountEmails(self.inbox.address()));
+    }
+
+    fn watch_config(&mut self) {
+        if !self.config_watcher.changed() {
+            return;
+        }
+        let (config, errors) = ConfigFile::load();
+        let proxy = self.settings.proxy().to_owned();
+        let api_url = self.settings.api_url().to_owned();
+        self.settings.overlay = config.merge(&self.cli_config);
+        self.status = config_status(errors);
+        if self.settings.proxy() != proxy {
+            self.send_event(Event::SetProxy(self.settings.proxy().into()));
+        }
+        if self.settings.api_url() != api_url {
+            self.send_event(Event::SetApiUrl(self.settings.api_url().into()));
+        }
+    }
+
+    fn poll(&mut self) {
+        let interval = self.settings.poll_interval_secs();
+        if interval == 0 || self.last_poll.elapsed() < Duration::from_secs(interval) {
+            return;
+        }
+        self.last_poll = Instant::now();
+        self.polling = true;
+        let _ = self.events.send(Event::FetchEmails(self.inbox.address()));
+        let _ = self.events.send(Event::CountEmails(self.inbox.address()));
+    }
+
+    fn handle_responses(&mut self) {
+        while let Ok(response) = self.responses.try_recv() {
+            self.fetching = false;
+            if matches!(
+                response,
+                EventResponse::Emails(..) | EventResponse::Error(_)
+            ) {
+                self.polling = false;
+            }
+            if let EventResponse::AttachmentSaved(path) = &response {
+                self.status = Some(saved_status(path));
+            }
+            if let Err(message) = self.inbox.apply(response) {
+                self.status = Some(message);
+            }
+        }
+        let last = self.inbox.emails.len().checked_sub(1);
+        match (self.list.selected(), last) {
+            (_, None) => self.list.select(None),
+            (None, Some(_)) => self.list.select(Some(0)),
+            (Some(selected), Some(last)) => self.list.select(Some(selected.min(last))),
+        }
+    }
+
+    // Returns false when the user wants to quit.
+    fn handle_key(&mut self, key: KeyCode) -> bool {
+        self.status = None;
+        if let Some(picker) = &mut self.domain_picker {
+            match key {
+                KeyCode::Up | KeyCode::Char('k') => picker.select_previous(),
+                KeyCode::Down | KeyCode::Char('j') => picker.select_next(),
+                KeyCode::Enter => {
+                    let domain = picker
+                        .selected()
+                        .and_then(|index| self.inbox.domains.get(index));
+                    if let Some(domain) = domain.cloned() {
+                        self.switch_address(self.inbox.name.clone(), domain);
+                    }
+                    self.domain_picker = None;
+                }
+                KeyCode::Esc | KeyCode::Char('q') => self.domain_picker = None,
+                _ => {}
+            }
+            return true;
+        }
+        if key == KeyCode::Char('q') {
+            return false;
+        }
+        match &self.inbox.viewed_email {
+            Some(email) => self.view_key(key, email.clone()),
+            None => self.list_key(key),
+        }
+        true
+    }
+
+    fn list_key(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => self.list.select_previous(),
+            KeyCode::Down | KeyCode::Char('j') => self.list.select_next(),
+            KeyCode::Enter => {
+                if let Some(email) = self.selected_email() {
+                    self.inbox.attachments = None;
+                    self.scroll = 0;
+                    self.send_event(Event::FetchEmail(email.id));
+                }
+            }
+            KeyCode::Char('r') => self.refresh(),
+            KeyCode::Char('n') => self.switch_address(random_name(10), self.inbox.domain.clone()),
+            KeyCode::Char('d') => {
+                let current = self
+                    .inbox
+                    .domains
+                    .iter()
+                    .position(|domain| *domain == self.inbox.domain);
+                self.domain_picker = Some(ListState::default().with_selected(current.or(Some(0))));
+            }
+            KeyCode::Char('x') => {
+                if let Some(email) = self.selected_email() {
+                    self.send_event(Event::DeleteEmail(email.id));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn view_key(&mut self, key: KeyCode, email: Email) {
+        match key {
+            KeyCode::Esc | KeyCode::Backspace | KeyCode::Left => {
+                self.inbox.viewed_email = None;
+                self.inbox.attachments = None;
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => self.scroll = self.scroll.saturating_add(1),
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(10),
+            KeyCode::PageDown => self.scroll = self.scroll.saturating_add(10),
+            KeyCode::Char('a') if email.has_attachments => {
+                self.send_event(Event::GetAttachments(email.id))
+            }
+            KeyCode::Char(key @ ('s' | 'S')) => {
+                let dir = match &self.private {
+                    Some(dir) => dir.path().into(),
+                    None => self.settings.download_dir(),
+                };
+                let limit = match key {
+                    's' => self.settings.max_download_bytes(),
+                    _ => 0,
+                };
+                let mut skipped = 0;
+                for attachment in self.inbox.attachments.clone().unwrap_or_default() {
+                    if limit > 0 && attachment.size > limit {
+                        skipped += 1;
+                        continue;
+                    }
+                    let path = dir.join(safe_filename(&attachment));
+                    self.send_event(Event::DownloadAttachment(attachment.id, path, limit));
+                }
+                if skipped > 0 {
+                    let limit = self.settings.max_download_mb.to_string();
+                    let skipped = skipped.to_string();
+                    self.status = Some(tr_with(
+                        "tui_skipped_large",
+                        &[("count", &skipped), ("limit", &limit)],
+                    ));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn switch_address(&mut self, name: String, domain: String) {
+        self.inbox.name = name;
+        self.inbox.domain = domain;
+        self.inbox.emails.clear();
+        self.inbox.email_count = 0;
+        self.refresh();
+    }
+
+    fn selected_email(&self) -> Option<Email> {
+        self.inbox.emails.get(self.list.selected()?).cloned()
+    }
+}
+
+impl Tui {
+    fn draw(&mut self, frame: &mut Frame) {
+        let [top, body, bottom] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        self.top_bar(frame, top);
+        match self.inbox.viewed_email.clone() {
+            Some(email) => self.email_view(frame, body, &email),
+            None => self.email_list(frame, body),
+        }
+        let bottom_line = match &self.status {
+            Some(status) => Line::from(status.as_str()).fg(Color::Red),
+            None if self.inbox.viewed_email.is_some() => Line::from(tr("tui_help_view")).dim(),
+            None => Line::from(tr("tui_help_list")).dim(),
+        };
+        frame.render_widget(bottom_line, bottom);
+        self.domain_picker(frame);
+    }
+
+    fn top_bar(&self, frame: &mut Frame, area: Rect) {
+        let count = self.inbox.email_count.to_string();
+        let mut line = Line::from(vec![
+            Span::from(self.inbox.address()).bold(),
+            Span::from("  "),
+            Span::from(tr_with("tui_count", &[("count", &count)])).dim(),
+        ]);
+        if self.fetching || self.polling {
+            line.push_span(Span::from(format!("  {}", tr("tui_loading"))).italic());
+        }
+        frame.render_widget(Paragraph::new(line).block(Block::bordered()), area);
+    }
+
+    fn email_list(&mut self, frame: &mut Frame, area: Rect) {
+        if self.inbox.emails.is_empty() {
+            let empty = Paragraph::new(tr("list_empty")).block(Block::bordered());
+            frame.render_widget(empty, area);
+            return;
+        }
+        let dates = self.settings.date_formatter();
+        let items = self.inbox.emails.iter().map(|email| {
+            let mut line = Line::from(vec![
+                Span::from(dates.format(email.received_at)).dim(),
+                Span::from("  "),
+                Span::from(email.from_address.as_str()),
+                Span::from("  "),
+                Span::from(email.subject.as_str()).bold(),
+            ]);
+            if let Some(code) = inbox::verification_code(email) {
+                line.push_span(Span::from("  "));
+                line.push_span(Span::styled(code, code_style()));
+            }
+            ListItem::new(line)
+        });
+        let list = List::new(items)
+            .block(Block::bordered())
+            .highlight_symbol("> ")
+            .highlight_style(Style::new().reversed());
+        frame.render_stateful_widget(list, area, &mut self.list);
+    }
+
+    fn email_view(&self, frame: &mut Frame, area: Rect, email: &Email) {
+        let dates = self.settings.date_formatter();
+        let code = inbox::verification_code(email);
+        let mut lines = vec![
+            Line::from(email.subject.as_str()).bold(),
+            Line::from(format!("{:10} {}", tr("view_from"), email.from_address)),
+            Line::from(format!("{:10} {}", tr("view_to"), email.to_address)),
+            Line::from(dates.format(email.received_at)).dim(),
+        ];
+        if let Some(code) = &code {
+            lines.push(Line::from(vec![
+                Span::from(format!("{:10} ", tr("view_code"))),
+                Span::styled(code.clone(), code_style()),
+            ]));
+        }
+        if let Some(link) = inbox::verification_link(email) {
+            lines.push(Line::from(format!("{:10} {link}", tr("view_link"))));
+        }
+        lines.push(Line::default());
+
+        let body = email
+            .text_content
+            .as_deref()
+            .or(email.html_content.as_deref())
+            .unwrap_or_default();
+        lines.extend(body.lines().map(|line| highlighted(line, code.as_deref())));
+
+        match &self.inbox.attachments {
+            Some(attachments) => {
+                lines.push(Line::default());
+                lines.push(Line::from(tr("tui_attachments")).bold());
+                lines.extend(attachments.iter().map(|attachment| {
+                    Line::from(format!(
+                        "{}  {}  {}",
+                        attachment.filename,
+                        UnitSize(attachment.size),
+                        attachment.content_type
+                    ))
+                }));
+            }
+            None if email.has_attachments => {
+                let count = email.attachment_count.to_string();
+                lines.push(Line::default());
+                lines.push(Line::from(tr_with("tui_attachments_hint", &[("count", &count)])).dim());
+            }
+            None => {}
+        }
+
+        let view = Paragraph::new(lines)
+            .block(Block::bordered())
+            .wrap(Wrap { trim: false })
+            .scroll((self.scroll, 0));
+        frame.render_widget(view, area);
+    }
+
+    fn domain_picker(&mut self, frame: &mut Frame) {
+        let Some(picker) = &mut self.domain_picker else {
+            return;
+        };
+        let height = (self.inbox.domains.len() as u16 + 2).min(15);
+        let area = centered(frame.area(), 40, height);
+        let list = List::new(self.inbox.domains.iter().map(String::as_str))
+            .block(Block::bordered().title(tr("tui_domains")))
+            .highlight_symbol("> ")
+            .highlight_style(Style::new().reversed());
+        frame.render_widget(Clear, area);
+        frame.render_stateful_widget(list, area, picker);
+    }
+}
+
+/// Asks for the passphrase of an encrypted state before the screen starts.
+fn unlock(state: &mut SavedState) -> io::Result<Option<VaultKey>> {
+    if state.vault.is_none() {
+        return Ok(None);
+    }
+    for _ in 0..UNLOCK_ATTEMPTS {
+        let passphrase = Zeroizing::new(read_hidden(&tr("lock_encrypted_prompt"))?);
+        match state.unlock(&passphrase) {
+            Ok(key) => return Ok(key),
+            Err(message) => eprintln!("{message}"),
+        }
+    }
+    Err(io::Error::other("Could not unlock the stored addresses"))
+}
+
+fn read_hidden(prompt: &str) -> io::Result<String> {
+    eprint!("{prompt}");
+    terminal::enable_raw_mode()?;
+    let mut input = String::new();
+    let result = loop {
+        let key = match event::read() {
+            Ok(event::Event::Key(key)) if key.kind == KeyEventKind::Press => key,
+            Ok(_) => continue,
+            Err(e) => break Err(e),
+        };
+        match key.code {
+            KeyCode::Enter => break Ok(input),
+            KeyCode::Esc => break Err(io::Error::from(io::ErrorKind::Interrupted)),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                break Err(io::Error::from(io::ErrorKind::Interrupted));
+            }
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            _ => {}
+        }
+    };
+    terminal::disable_raw_mode()?;
+    eprintln!();
+    result
+}
+
+fn saved_status(path: &Path) -> String {
+    let saved = tr_with("tui_saved", &[("path", &path.display().to_string())]);
+    match check_file(path) {
+        Ok(check) => {
+            let status = format!("{saved} · SHA-256 {}", check.sha256);
+            match check.risk {
+                Some(risk) => format!("⚠ {} · {status}", risk.describe()),
+                None => status,
+            }
+        }
+        Err(_) => saved,
+    }
+}
+
+fn config_status(errors: Vec<String>) -> Option<String> {
+    (!errors.is_empty()).then(|| tr_with("config_invalid", &[("errors", &errors.join("; "))]))
+}
+
+fn code_style() -> Style {
+    Style::new().fg(Color::Yellow).bold()
+}
+
+fn highlighted<'a>(line: &'a str, code: Option<&str>) -> Line<'a> {
+    let Some(code) = code.filter(|code| line.contains(code)) else {
+        return Line::from(line);
+    };
+    let mut spans = Vec::new();
+    for (index, part) in line.split(code).enumerate() {
+        if index > 0 {
+            spans.push(Span::styled(code.to_owned(), code_style()));
+        }
+        spans.push(Span::from(part));
+    }
+    Line::from(spans)
+}
+
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let [area] = Layout::horizontal([Constraint::Length(width)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [area] = Layout::vertical([Constraint::Length(height)])
+        .flex(Flex::Center)
+        .areas(area);
+    area
+}
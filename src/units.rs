@@ -0,0 +1,22 @@
+//! Human readable sizes.
+
+use std::fmt::{Display, Write};
+
+/// A size in bytes, displayed with binary units, e.g. `1.50KB`.
+pub struct UnitSize(pub u64);
+
+impl Display for UnitSize {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let val = self.0 as f64;
+        const KILO: f64 = 1024.;
+        const MEGA: f64 = KILO * KILO;
+        const GIGA: f64 = KILO * MEGA;
+        match val {
+            0. => fmt.write_char('0'),
+            val if val < KILO => write!(fmt, "{}B", val as u64),
+            val if val < MEGA => write!(fmt, "{:.2}KB", val / KILO),
+            val if val < GIGA => write!(fmt, "{:.2}MB", val / MEGA),
+            val => write!(fmt, "{:.2}GB", val / GIGA),
+        }
+    }
+}
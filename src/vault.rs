@@ -0,0 +1,98 @@
+//! Encrypting the addresses and their per-address settings with a
+//! passphrase before the state is saved.
+
+use std::collections::BTreeMap;
+
+use argon2::Argon2;
+use base64::{Engine, prelude::BASE64_STANDARD};
+use chacha20poly1305::{
+    Key, XChaCha20Poly1305, XNonce,
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use crate::{forward::Forwarding, retention::Retention};
+
+const SEALED_PREFIX: &str = "v1:";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+/// The part of the saved state that is encrypted once a passphrase is set.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Vault {
+    pub name: Option<String>,
+    pub domain: Option<String>,
+    pub recent_addresses: Vec<String>,
+    pub retention: BTreeMap<String, Retention>,
+    pub forwarding: BTreeMap<String, Forwarding>,
+}
+
+/// A key derived from the passphrase with Argon2id. It is kept while the
+/// vault is unlocked, so saving does not derive it again.
+pub struct VaultKey {
+    salt: [u8; SALT_LEN],
+    key: Zeroizing<[u8; 32]>,
+}
+
+impl VaultKey {
+    pub fn new(passphrase: &str) -> Result<Self, String> {
+        if passphrase.is_empty() {
+            return Err("The passphrase is empty".into());
+        }
+        let mut salt = [0; SALT_LEN];
+        rand::rng().fill(&mut salt);
+        Self::derive(passphrase, salt)
+    }
+
+    fn derive(passphrase: &str, salt: [u8; SALT_LEN]) -> Result<Self, String> {
+        let mut key = Zeroizing::new([0; 32]);
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), &salt, key.as_mut())
+            .map_err(|e| format!("Could not derive the key: {e}"))?;
+        Ok(Self { salt, key })
+    }
+
+    fn cipher(&self) -> XChaCha20Poly1305 {
+        XChaCha20Poly1305::new(Key::from_slice(self.key.as_ref()))
+    }
+}
+
+impl Vault {
+    /// Encrypts the vault as `v1:` followed by the base64 of the salt, the
+    /// nonce and the ciphertext.
+    pub fn seal(&self, key: &VaultKey) -> String {
+        let plain = Zeroizing::new(ron::to_string(self).unwrap_or_default());
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let sealed = key
+            .cipher()
+            .encrypt(&nonce, plain.as_bytes())
+            .expect("encrypting into memory does not fail");
+        let bytes = [key.salt.as_slice(), nonce.as_slice(), &sealed].concat();
+        format!("{SEALED_PREFIX}{}", BASE64_STANDARD.encode(bytes))
+    }
+
+    /// Decrypts a sealed vault, returning the key to seal it again.
+    pub fn open(sealed: &str, passphrase: &str) -> Result<(Self, VaultKey), String> {
+        let bytes = sealed
+            .strip_prefix(SEALED_PREFIX)
+            .and_then(|sealed| BASE64_STANDARD.decode(sealed).ok())
+            .filter(|bytes| bytes.len() > SALT_LEN + NONCE_LEN)
+            .ok_or("The encrypted data is damaged")?;
+        let (salt, rest) = bytes.split_at(SALT_LEN);
+        let (nonce, sealed) = rest.split_at(NONCE_LEN);
+        let key = VaultKey::derive(passphrase, salt.try_into().unwrap())?;
+        let plain = key
+            .cipher()
+            .decrypt(XNonce::from_slice(nonce), sealed)
+            .map(Zeroizing::new)
+            .map_err(|_| "Wrong passphrase")?;
+        let vault = std::str::from_utf8(&plain)
+            .ok()
+            .and_then(|plain| ron::from_str(plain).ok())
+            .ok_or("The encrypted data is damaged")?;
+        Ok((vault, key))
+    }
+}
@@ -0,0 +1,59 @@
+use std::{fs, io::Write, path::PathBuf};
+
+use flate2::{Compression, write::GzEncoder};
+use tmgui::archives::{Kind, enclosed, extract, list};
+
+#[test]
+fn entries_inside_the_directory() {
+    assert_eq!(enclosed("a.txt"), Some(PathBuf::from("a.txt")));
+    assert_eq!(enclosed("docs/./a.txt"), Some(PathBuf::from("docs/a.txt")));
+}
+
+#[test]
+fn entries_leaving_the_directory() {
+    assert_eq!(enclosed("../a.txt"), None);
+    assert_eq!(enclosed("docs/../../a.txt"), None);
+    assert_eq!(enclosed("/etc/passwd"), None);
+    assert_eq!(enclosed(""), None);
+    assert_eq!(enclosed("."), None);
+}
+
+#[test]
+fn kind_by_name() {
+    assert!(Kind::of("a.ZIP") == Some(Kind::Zip));
+    assert!(Kind::of("a.tar.gz") == Some(Kind::TarGz));
+    assert!(Kind::of("a.tgz") == Some(Kind::TarGz));
+    assert!(Kind::of("a.log.gz") == Some(Kind::Gz));
+    assert!(Kind::of("a.tar") == Some(Kind::Tar));
+    assert!(Kind::of("a.txt").is_none());
+}
+
+#[test]
+fn gzip_listed_without_unpacking() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("log.txt.gz");
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&[b'a'; 100_000]).unwrap();
+    fs::write(&path, encoder.finish().unwrap()).unwrap();
+    let entries = list(&path, Kind::Gz).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].name, "log.txt");
+    assert_eq!(entries[0].size, 100_000);
+}
+
+#[test]
+fn extracting_keeps_existing_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("a.txt.gz");
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(b"new").unwrap();
+    fs::write(&path, encoder.finish().unwrap()).unwrap();
+    let target = dir.path().join("out");
+    fs::create_dir(&target).unwrap();
+    fs::write(target.join("a.txt"), "old").unwrap();
+    let names = ["a.txt".to_owned()];
+    let extracted = extract(&path, Kind::Gz, &names, &target).unwrap();
+    assert_eq!(extracted, [target.join("a (1).txt")]);
+    assert_eq!(fs::read_to_string(target.join("a.txt")).unwrap(), "old");
+    assert_eq!(fs::read_to_string(&extracted[0]).unwrap(), "new");
+}
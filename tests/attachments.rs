@@ -0,0 +1,44 @@
+use tmgui::attachments::{Risk, id_dir, sniff};
+
+#[test]
+fn executables() {
+    assert!(sniff(b"MZ\x90\x00\x03") == Some(Risk::Executable));
+    assert!(sniff(b"\x7fELF\x02\x01") == Some(Risk::Executable));
+    assert!(sniff(&[0xcf, 0xfa, 0xed, 0xfe, 7]) == Some(Risk::Executable));
+    assert!(sniff(b"PK\x03\x04....META-INF/MANIFEST.MF") == Some(Risk::Executable));
+}
+
+#[test]
+fn scripts() {
+    assert!(sniff(b"#!/bin/sh\nrm -rf ~") == Some(Risk::Script));
+    assert!(sniff(b"<html><SCRIPT>alert(1)</script>") == Some(Risk::Script));
+    assert!(sniff(b"%PDF-1.7 /OpenAction << /JavaScript >>") == Some(Risk::Script));
+}
+
+#[test]
+fn macros() {
+    assert!(sniff(b"PK\x03\x04....word/vbaProject.bin") == Some(Risk::Macro));
+    let mut ole = b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1".to_vec();
+    ole.extend(b"_\0V\0B\0A\0_\0P\0R\0O\0J\0E\0C\0T\0");
+    assert!(sniff(&ole) == Some(Risk::Macro));
+}
+
+#[test]
+fn harmless_files() {
+    assert!(sniff(b"\x89PNG\r\n\x1a\n").is_none());
+    assert!(sniff(b"%PDF-1.7 plain document").is_none());
+    assert!(sniff(b"PK\x03\x04....word/document.xml").is_none());
+    assert!(sniff(b"Hello, world").is_none());
+    assert!(sniff(b"").is_none());
+}
+
+#[test]
+fn id_dirs_stay_in_place() {
+    for id in ["../../.ssh", "/etc/passwd", "a\\b", ""] {
+        let dir = id_dir(id);
+        assert_eq!(dir.len(), 32);
+        assert!(dir.chars().all(|c| c.is_ascii_hexdigit()));
+    }
+    assert_eq!(id_dir("abc"), id_dir("abc"));
+    assert_ne!(id_dir("abc"), id_dir("abd"));
+}
@@ -0,0 +1,135 @@
+use chrono::NaiveDate;
+use tmgui::cards::{EventTime, contacts, events};
+
+const INVITE: &str = "BEGIN:VCALENDAR\r
+VERSION:2.0\r
+BEGIN:VEVENT\r
+SUMMARY:Planning\\, Q3\r
+DTSTART;TZID=Europe/Berlin:20250704T100000\r
+DTEND:20250704T090000Z\r
+LOCATION:Room 4\r
+ORGANIZER;CN=\"Doe, Jane\":mailto:jane@example.com\r
+RRULE:FREQ=WEEKLY;COUNT=3\r
+DESCRIPTION:First line\\nsecond line that is folded \r
+ onto the next\r
+BEGIN:VALARM\r
+DESCRIPTION:Reminder\r
+END:VALARM\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+SUMMARY:Holiday\r
+DTSTART;VALUE=DATE:20251224\r
+STATUS:CANCELLED\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+
+#[test]
+fn reads_events() {
+    let events = events(INVITE);
+    assert_eq!(events.len(), 2);
+
+    let planning = &events[0];
+    assert_eq!(planning.summary, "Planning, Q3");
+    // 10:00 in Berlin summer time is 08:00 UTC.
+    assert!(matches!(planning.start, Some(EventTime::At(1751616000))));
+    assert!(matches!(planning.end, Some(EventTime::At(1751619600))));
+    assert_eq!(planning.location.as_deref(), Some("Room 4"));
+    assert_eq!(
+        planning.organizer.as_deref(),
+        Some("Doe, Jane <jane@example.com>")
+    );
+    assert_eq!(planning.recurrence.as_deref(), Some("FREQ=WEEKLY;COUNT=3"));
+    assert_eq!(
+        planning.description.as_deref(),
+        Some("First line\nsecond line that is folded onto the next")
+    );
+    assert!(!planning.cancelled);
+
+    let holiday = &events[1];
+    let day = NaiveDate::from_ymd_opt(2025, 12, 24).unwrap();
+    assert!(matches!(holiday.start, Some(EventTime::Date(date)) if date == day));
+    assert!(holiday.cancelled);
+}
+
+#[test]
+fn cancelled_calendar() {
+    let text =
+        "BEGIN:VCALENDAR\nMETHOD:CANCEL\nBEGIN:VEVENT\nSUMMARY:Sync\nEND:VEVENT\nEND:VCALENDAR\n";
+    assert!(events(text)[0].cancelled);
+}
+
+#[test]
+fn reads_contacts() {
+    let text = "BEGIN:VCARD\r
+VERSION:4.0\r
+N:Doe;John;;;\r
+ORG:Example Inc.;Sales\r
+item1.EMAIL;TYPE=work:john@example.com\r
+TEL;VALUE=uri:tel:+1-555-0100\r
+END:VCARD\r
+BEGIN:VCARD\r
+FN:Jane Roe\r
+END:VCARD\r
+";
+    let contacts = contacts(text);
+    assert_eq!(contacts.len(), 2);
+    assert_eq!(contacts[0].name, "John Doe");
+    assert_eq!(
+        contacts[0].organization.as_deref(),
+        Some("Example Inc., Sales")
+    );
+    assert_eq!(contacts[0].emails, ["john@example.com"]);
+    assert_eq!(contacts[0].phones, ["+1-555-0100"]);
+    assert!(contacts[0].source.starts_with("BEGIN:VCARD\r\n"));
+    assert_eq!(contacts[1].name, "Jane Roe");
+    assert!(contacts[1].emails.is_empty());
+}
+
+#[test]
+fn windows_zone_names() {
+    let text = "BEGIN:VEVENT\nDTSTART;TZID=W. Europe Standard Time:20250704T100000\nEND:VEVENT\n";
+    assert!(matches!(
+        events(text)[0].start,
+        Some(EventTime::At(1751616000))
+    ));
+}
+
+#[test]
+fn zones_of_the_calendar() {
+    let text = "BEGIN:VCALENDAR\r
+BEGIN:VTIMEZONE\r
+TZID:Custom Berlin\r
+BEGIN:STANDARD\r
+DTSTART:16011028T030000\r
+RRULE:FREQ=YEARLY;BYDAY=-1SU;BYMONTH=10\r
+TZOFFSETFROM:+0200\r
+TZOFFSETTO:+0100\r
+END:STANDARD\r
+BEGIN:DAYLIGHT\r
+DTSTART:16010325T020000\r
+RRULE:FREQ=YEARLY;BYDAY=-1SU;BYMONTH=3\r
+TZOFFSETFROM:+0100\r
+TZOFFSETTO:+0200\r
+END:DAYLIGHT\r
+END:VTIMEZONE\r
+BEGIN:VEVENT\r
+DTSTART;TZID=Custom Berlin:20250704T100000\r
+DTEND;TZID=Custom Berlin:20251224T100000\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+    let event = &events(text)[0];
+    // Summer time in July, standard time in December.
+    assert!(matches!(event.start, Some(EventTime::At(1751616000))));
+    assert!(matches!(event.end, Some(EventTime::At(1766566800))));
+}
+
+#[test]
+fn unknown_zones_float() {
+    let text = "BEGIN:VEVENT\nDTSTART;TZID=Nowhere:20250704T100000\nEND:VEVENT\n";
+    assert!(matches!(
+        events(text)[0].start,
+        Some(EventTime::Floating(_))
+    ));
+}
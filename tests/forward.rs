@@ -0,0 +1,26 @@
+use tmgui::forward::{Forwarding, Security};
+
+fn forwarding(security: Security, username: &str) -> Forwarding {
+    Forwarding {
+        host: "smtp.example.com".into(),
+        port: security.default_port(),
+        security,
+        username: username.into(),
+        password: "secret".into(),
+        from: "me@example.com".into(),
+        to: "inbox@example.com".into(),
+        automatic: false,
+    }
+}
+
+#[test]
+fn refuses_login_without_encryption() {
+    assert!(forwarding(Security::None, "me").validate().is_err());
+    assert!(forwarding(Security::StartTls, "me").validate().is_ok());
+    assert!(forwarding(Security::Tls, "me").validate().is_ok());
+}
+
+#[test]
+fn sends_without_login_unencrypted() {
+    assert!(forwarding(Security::None, "").validate().is_ok());
+}
@@ -0,0 +1,52 @@
+use tmapi::Email;
+use tmgui::inbox::{verification_code, verification_link};
+
+fn email(subject: &str, text: &str) -> Email {
+    Email {
+        id: "1".into(),
+        from_address: "noreply@example.com".into(),
+        to_address: "user@example.com".into(),
+        subject: subject.into(),
+        received_at: 0,
+        html_content: None,
+        text_content: Some(text.into()),
+        has_attachments: false,
+        attachment_count: 0,
+    }
+}
+
+#[test]
+fn code_after_keyword() {
+    let email = email("Welcome", "Order 12345 shipped. Your code is 482913.");
+    assert_eq!(verification_code(&email).as_deref(), Some("482913"));
+}
+
+#[test]
+fn code_in_subject() {
+    let email = email("Your verification code: AB12CD", "Thanks for signing up.");
+    assert_eq!(verification_code(&email).as_deref(), Some("AB12CD"));
+}
+
+#[test]
+fn any_number_without_keyword() {
+    let email = email("Welcome", "Enter 7731 to continue.");
+    assert_eq!(verification_code(&email).as_deref(), Some("7731"));
+}
+
+#[test]
+fn no_code() {
+    let email = email("Hello", "Nothing to see here.");
+    assert_eq!(verification_code(&email), None);
+}
+
+#[test]
+fn confirmation_link_first() {
+    let email = email(
+        "Welcome",
+        "Read https://example.com/blog or confirm at https://example.com/verify?t=1&amp;u=2.",
+    );
+    assert_eq!(
+        verification_link(&email).as_deref(),
+        Some("https://example.com/verify?t=1&u=2")
+    );
+}
@@ -0,0 +1,59 @@
+use tmgui::links::{LinkWarning, check, strip_tracking};
+
+#[test]
+fn plain_link_is_fine() {
+    assert!(check("https://example.com/login", Some("example.com")).is_empty());
+    assert!(check("https://www.example.com/", Some("example.com")).is_empty());
+}
+
+#[test]
+fn text_naming_another_domain() {
+    let warnings = check("https://evil.test/login", Some("Log in at bank.com"));
+    assert!(matches!(&warnings[..], [LinkWarning::TextMismatch(shown)] if shown == "bank.com"));
+}
+
+#[test]
+fn credentials_and_ip_addresses() {
+    let warnings = check("https://bank.com@evil.test/", None);
+    assert!(matches!(&warnings[..], [LinkWarning::Credentials]));
+    let warnings = check("http://192.168.1.10/reset", None);
+    assert!(matches!(&warnings[..], [LinkWarning::IpAddress]));
+}
+
+#[test]
+fn cyrillic_lookalike() {
+    // The first letter is a Cyrillic а.
+    let warnings = check("https://\u{430}pple.com/", None);
+    assert!(
+        warnings
+            .iter()
+            .any(|warning| matches!(warning, LinkWarning::Punycode(_)))
+    );
+    assert!(
+        warnings.iter().any(
+            |warning| matches!(warning, LinkWarning::Lookalike(ascii) if ascii == "apple.com")
+        )
+    );
+}
+
+#[test]
+fn strips_tracking_parameters() {
+    assert_eq!(
+        strip_tracking("https://example.com/a?utm_source=mail&id=7&fbclid=x"),
+        "https://example.com/a?id=7"
+    );
+    assert_eq!(
+        strip_tracking("https://example.com/a?utm_medium=email"),
+        "https://example.com/a"
+    );
+}
+
+#[test]
+fn keeps_links_without_tracking() {
+    let url = "https://example.com/a?b=1&c=2";
+    assert_eq!(strip_tracking(url), url);
+    assert_eq!(
+        strip_tracking("not a url?utm_source=x"),
+        "not a url?utm_source=x"
+    );
+}
@@ -0,0 +1,30 @@
+use tmgui::retention::{OpenedInbox, Retention};
+
+#[test]
+fn keeps_everything_by_default() {
+    let retention = Retention::default();
+    assert!(retention.is_empty());
+    assert!(!retention.expired(0, i64::MAX / 2));
+}
+
+#[test]
+fn expires_after_max_age() {
+    let retention = Retention {
+        max_age_hours: 2,
+        ..Default::default()
+    };
+    let now = 1_700_000_000;
+    assert!(!retention.expired(now - 2 * 3600, now));
+    assert!(retention.expired(now - 2 * 3600 - 1, now));
+    assert!(!retention.expired(now + 60, now));
+}
+
+#[test]
+fn leaves_only_when_another_inbox_opens() {
+    let mut opened = OpenedInbox::default();
+    assert_eq!(opened.open("real@vwh.sh"), None);
+    // Typing in the address field opens nothing, so only the next loaded
+    // inbox leaves the previous one.
+    assert_eq!(opened.open("real@vwh.sh"), None);
+    assert_eq!(opened.open("other@vwh.sh").as_deref(), Some("real@vwh.sh"));
+}
@@ -0,0 +1,47 @@
+use tmapi::Email;
+use tmgui::{event_handler::EventResponse, state::InboxState};
+
+fn email(id: &str) -> Email {
+    Email {
+        id: id.into(),
+        from_address: "noreply@example.com".into(),
+        to_address: "user@example.com".into(),
+        subject: "Hello".into(),
+        received_at: 0,
+        html_content: None,
+        text_content: None,
+        has_attachments: false,
+        attachment_count: 0,
+    }
+}
+
+#[test]
+fn deletes_by_id_after_reload() {
+    let mut inbox = InboxState {
+        emails: vec![email("a"), email("b"), email("c")],
+        ..Default::default()
+    };
+    // The list was reloaded with a new email on top after the request.
+    inbox.emails.insert(0, email("new"));
+    inbox
+        .apply(EventResponse::EmailDeleted("b".into()))
+        .unwrap();
+    let ids = inbox
+        .emails
+        .iter()
+        .map(|e| e.id.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(ids, ["new", "a", "c"]);
+}
+
+#[test]
+fn deleting_a_gone_email_keeps_the_rest() {
+    let mut inbox = InboxState {
+        emails: vec![email("a")],
+        ..Default::default()
+    };
+    inbox
+        .apply(EventResponse::EmailDeleted("b".into()))
+        .unwrap();
+    assert_eq!(inbox.emails.len(), 1);
+}
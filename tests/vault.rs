@@ -0,0 +1,45 @@
+use tmgui::{
+    retention::Retention,
+    vault::{Vault, VaultKey},
+};
+
+#[test]
+fn seal_and_open() {
+    let mut vault = Vault {
+        name: Some("user".into()),
+        domain: Some("example.com".into()),
+        recent_addresses: vec!["user@example.com".into()],
+        ..Default::default()
+    };
+    vault.retention.insert(
+        "user@example.com".into(),
+        Retention {
+            max_age_hours: 24,
+            ..Default::default()
+        },
+    );
+    let key = VaultKey::new("correct horse").unwrap();
+    let sealed = vault.seal(&key);
+    assert!(sealed.starts_with("v1:"));
+    assert!(!sealed.contains("example.com"));
+
+    let (opened, key) = Vault::open(&sealed, "correct horse").unwrap();
+    assert_eq!(opened.name.as_deref(), Some("user"));
+    assert_eq!(opened.recent_addresses, vault.recent_addresses);
+    assert!(opened.retention == vault.retention);
+    // The returned key seals again without deriving it anew.
+    assert!(Vault::open(&opened.seal(&key), "correct horse").is_ok());
+}
+
+#[test]
+fn wrong_passphrase_and_damage() {
+    let key = VaultKey::new("secret").unwrap();
+    let sealed = Vault::default().seal(&key);
+    assert_eq!(
+        Vault::open(&sealed, "guess").err().as_deref(),
+        Some("Wrong passphrase")
+    );
+    assert!(Vault::open(&sealed[..sealed.len() - 8], "secret").is_err());
+    assert!(Vault::open("v1:AAAA", "secret").is_err());
+    assert!(VaultKey::new("").is_err());
+}